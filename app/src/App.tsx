@@ -37,6 +37,7 @@ function App() {
     if (!file) return
     Plan.read_file(file, format, schema)
       .then(plan => plan.limit(0).collect())
+      .then(recordSet => recordSet.with_page_cache(32))
       .then(setRecordSet)
   }
 
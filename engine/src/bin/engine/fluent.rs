@@ -0,0 +1,129 @@
+use engine::Plan;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// A call in a chain such as `filter(...).select(["a", "b"]).limit(0, 10)`.
+///
+/// Chains call the same methods as the JavaScript API, with the arguments written as JSON, so a
+/// query from the browser app can be pasted as is.
+#[derive(Debug, PartialEq)]
+pub struct Step {
+    pub method: String,
+    pub args: Vec<Value>,
+}
+
+/// Splits a chain of calls into its steps.
+pub fn parse(text: &str) -> Result<Vec<Step>, String> {
+    let mut steps = vec![];
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let open = rest
+            .find('(')
+            .ok_or_else(|| format!("expected `(` after `{rest}`"))?;
+        let method = rest[..open].trim();
+        if method.is_empty()
+            || !method
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(format!("invalid method name: `{method}`"));
+        }
+
+        let close = open + closing_paren(&rest[open..])?;
+        let args = serde_json::from_str(&format!("[{}]", &rest[open + 1..close]))
+            .map_err(|err| format!("invalid arguments to `{method}`: {err}"))?;
+        steps.push(Step { method: method.to_string(), args });
+
+        rest = rest[close + 1..].trim_start();
+        if let Some(next) = rest.strip_prefix('.') {
+            rest = next.trim_start();
+            if rest.is_empty() {
+                return Err("expected a method after `.`".to_string());
+            }
+        } else if !rest.is_empty() {
+            return Err(format!("expected `.` before `{rest}`"));
+        }
+    }
+    Ok(steps)
+}
+
+/// Applies each step to `plan`, in order.
+pub fn apply(steps: Vec<Step>, mut plan: Plan) -> Result<Plan, String> {
+    for Step { method, args } in steps {
+        plan = match method.as_str() {
+            "filter" => plan.filter(arg(&method, &args, 0)?)?,
+            "select" => plan.select(arg(&method, &args, 0)?)?,
+            "limit" => plan.limit(arg(&method, &args, 0)?, arg(&method, &args, 1)?)?,
+            "flatten" => plan.flatten(arg(&method, &args, 0)?)?,
+            "unnest" => plan.unnest(arg(&method, &args, 0)?, arg(&method, &args, 1)?)?,
+            "extract" => plan.extract(arg(&method, &args, 0)?, arg(&method, &args, 1)?)?,
+            _ => return Err(format!("unknown method: `{method}`")),
+        };
+    }
+    Ok(plan)
+}
+
+/// Reads an argument, treating missing arguments as `null` so that optional ones can be omitted.
+fn arg<T: DeserializeOwned>(method: &str, args: &[Value], index: usize) -> Result<T, String> {
+    let value = args.get(index).cloned().unwrap_or(Value::Null);
+    serde_json::from_value(value)
+        .map_err(|err| format!("invalid argument {} to `{method}`: {err}", index + 1))
+}
+
+/// Finds the parenthesis that closes the one `text` starts with, ignoring those in strings.
+fn closing_paren(text: &str) -> Result<usize, String> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err("unclosed `(`".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parses_chains() {
+        let steps = parse(
+            r#"filter({"op": "contains", "column": "name", "value": "(a)"})
+                .select(["name", 0]) . limit(0, 10).flatten()"#,
+        )
+        .unwrap();
+        let methods: Vec<_> = steps.iter().map(|step| step.method.as_str()).collect();
+        assert_eq!(methods, ["filter", "select", "limit", "flatten"]);
+        assert_eq!(steps[0].args[0]["value"], "(a)");
+        assert_eq!(steps[1].args, [json!(["name", 0])]);
+        assert_eq!(steps[2].args, [json!(0), json!(10)]);
+        assert!(steps[3].args.is_empty());
+
+        assert_eq!(parse("  ").unwrap(), []);
+    }
+
+    #[test]
+    fn rejects_malformed_chains() {
+        assert!(parse("limit(0, 10").is_err());
+        assert!(parse("limit(0, 10).").is_err());
+        assert!(parse("limit(0, 10) select([])").is_err());
+        assert!(parse("limit(0, 10,)").is_err());
+        assert!(parse("select").is_err());
+        assert!(parse("a.b(1)").is_err());
+    }
+}
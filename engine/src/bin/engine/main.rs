@@ -0,0 +1,227 @@
+//! Runs the engine natively, to reproduce what the browser app does with a file.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use datafusion::arrow::util::pretty::pretty_format_batches_with_schema;
+use engine::{
+    infer, ExportFormat, FileFormat, InputSource, LocalFileSource, MalformedRowPolicy, Plan,
+};
+use futures::executor::block_on;
+
+mod fluent;
+
+#[derive(Parser)]
+#[command(
+    name = "engine",
+    about = "Inspects, queries and converts CSV, JSON and Parquet files"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Infers a file's format, encoding and schema.
+    Infer {
+        #[command(flatten)]
+        input: Input,
+        /// The number of records to infer the schema from. Defaults to all of them.
+        #[arg(long)]
+        max_records: Option<usize>,
+        /// Explains how each column's type was inferred.
+        #[arg(long)]
+        report: bool,
+    },
+    /// Prints the first rows of a file.
+    Head {
+        #[command(flatten)]
+        input: Input,
+        #[arg(short = 'n', long, default_value_t = 10)]
+        rows: usize,
+    },
+    /// Runs a query against a file, and prints or writes its results.
+    Query {
+        #[command(flatten)]
+        input: Input,
+        /// A SQL query, which refers to the file as the table `data`.
+        #[arg(long, conflicts_with = "plan", required_unless_present = "plan")]
+        sql: Option<String>,
+        /// A chain of plan methods with JSON arguments, as in the JavaScript API, such as
+        /// `select(["Owner"]).limit(0, 10)`.
+        #[arg(long)]
+        plan: Option<String>,
+        /// The file to write the results to. They are printed as a table if omitted.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// The format to write the results in. Defaults to the output file's extension.
+        #[arg(long, value_enum)]
+        to: Option<OutputFormat>,
+    },
+    /// Converts a file to another format.
+    Convert {
+        #[command(flatten)]
+        input: Input,
+        output: PathBuf,
+        /// The format to convert to. Defaults to the output file's extension.
+        #[arg(long, value_enum)]
+        to: Option<OutputFormat>,
+    },
+}
+
+#[derive(Args)]
+struct Input {
+    file: PathBuf,
+    /// The file's format as JSON, such as `{"format": "csv", "encoding": "utf-8", "hasHeaders":
+    /// false}`. Inferred from the file if omitted.
+    #[arg(long)]
+    format: Option<String>,
+    /// What to do with rows that cannot be read: `failFast`, `skipRow` or `nullFields`.
+    #[arg(long)]
+    on_malformed: Option<String>,
+}
+
+impl Input {
+    fn source(&self) -> Result<LocalFileSource, String> {
+        LocalFileSource::new(&self.file)
+    }
+
+    async fn format(&self, source: &dyn InputSource) -> Result<FileFormat, String> {
+        match &self.format {
+            Some(format) => serde_json::from_str(format).map_err(|err| err.to_string()),
+            None => infer::infer_format(source).await,
+        }
+    }
+
+    async fn read(&self) -> Result<Plan, String> {
+        let source = Arc::new(self.source()?);
+        let format = self.format(source.as_ref()).await?;
+        let (schema, _) = infer::infer_schema(source.as_ref(), &format, None, false).await?;
+        let on_malformed = match &self.on_malformed {
+            Some(policy) => Some(parse_policy(policy)?),
+            None => None,
+        };
+        Plan::read_source(source, format, schema, on_malformed).await
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    /// JSON lines, with one object per row.
+    Json,
+    Parquet,
+    /// The Arrow IPC file format.
+    Arrow,
+}
+
+impl OutputFormat {
+    fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+        Ok(match ext {
+            "csv" => OutputFormat::Csv,
+            "json" | "jsonl" | "ndjson" => OutputFormat::Json,
+            "parquet" => OutputFormat::Parquet,
+            "arrow" | "ipc" | "feather" => OutputFormat::Arrow,
+            ext => Err(format!("unknown file extension: {ext}"))?,
+        })
+    }
+}
+
+impl From<OutputFormat> for ExportFormat {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Csv => ExportFormat::Csv {
+                delimiter: None,
+                has_headers: None,
+                date_format: None,
+                timestamp_format: None,
+            },
+            OutputFormat::Json => ExportFormat::JsonLines,
+            OutputFormat::Parquet => ExportFormat::Parquet { compression: None },
+            OutputFormat::Arrow => ExportFormat::ArrowIpc,
+        }
+    }
+}
+
+/// Prints the results of a plan as a table, or writes them to a file.
+async fn write_results(
+    plan: Plan,
+    output: Option<&Path>,
+    to: Option<OutputFormat>,
+) -> Result<(), String> {
+    let Some(path) = output else {
+        let records = plan.collect().await?;
+        let table = pretty_format_batches_with_schema(records.schema().clone(), records.batches())
+            .map_err(|err| err.to_string())?;
+        println!("{table}");
+        return Ok(());
+    };
+    let format = match to {
+        Some(format) => format,
+        None => OutputFormat::from_path(path)?,
+    };
+    let bytes = plan.export_bytes(&format.into()).await?;
+    std::fs::write(path, bytes).map_err(|err| err.to_string())
+}
+
+fn parse_policy(policy: &str) -> Result<MalformedRowPolicy, String> {
+    serde_json::from_value(serde_json::Value::String(policy.to_string()))
+        .map_err(|err| err.to_string())
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|err| err.to_string())
+}
+
+async fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Infer { input, max_records, report } => {
+            let source = input.source()?;
+            let format = input.format(&source).await?;
+            println!("format: {}", to_json(&format)?);
+            if !matches!(format, FileFormat::Parquet { .. }) {
+                println!("encoding: {}", infer::infer_encoding(&source).await?);
+            }
+
+            let (schema, report) =
+                infer::infer_schema(&source, &format, max_records, report).await?;
+            println!("schema:");
+            for field in schema.fields() {
+                let nullable = if field.is_nullable() { "" } else { " not null" };
+                println!("  {}: {}{}", field.name(), field.data_type(), nullable);
+            }
+            if let Some(report) = report {
+                println!("report: {}", to_json(&report)?);
+            }
+        }
+        Command::Head { input, rows } => {
+            let plan = input.read().await?.limit(0, Some(rows))?;
+            write_results(plan, None, None).await?;
+        }
+        Command::Query { input, sql, plan, output, to } => {
+            let mut query = input.read().await?;
+            if let Some(sql) = sql {
+                query = query.sql(&sql, "data").await?;
+            }
+            if let Some(plan) = plan {
+                query = fluent::apply(fluent::parse(&plan)?, query)?;
+            }
+            write_results(query, output.as_deref(), to).await?;
+        }
+        Command::Convert { input, output, to } => {
+            write_results(input.read().await?, Some(&output), to).await?;
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = block_on(run(cli.command)) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, RecordBatch};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{
+    DataType, Field, Schema, DECIMAL128_MAX_PRECISION, DECIMAL256_MAX_PRECISION,
+};
+use datafusion::arrow::json::writer::{LineDelimited, WriterBuilder};
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tsify::Tsify;
+
+/// The largest integer that JavaScript numbers can represent exactly.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Controls how values are formatted as text.
+#[derive(Tsify, Clone, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplayOptions {
+    /// The text shown for null values. Defaults to an empty string.
+    #[tsify(optional)]
+    pub null_text: Option<String>,
+    /// A chrono format string for dates, such as `%d/%m/%Y`.
+    #[tsify(optional)]
+    pub date_format: Option<String>,
+    /// A chrono format string for times.
+    #[tsify(optional)]
+    pub time_format: Option<String>,
+    /// A chrono format string for timestamps without a time zone.
+    #[tsify(optional)]
+    pub timestamp_format: Option<String>,
+    /// A chrono format string for timestamps with a time zone, such as `%Y-%m-%d %H:%M %Z`.
+    #[tsify(optional)]
+    pub timestamp_tz_format: Option<String>,
+    /// Converts timestamps that have a time zone to this one before formatting them, such as
+    /// `Australia/Sydney` or `+10:00`.
+    #[tsify(optional)]
+    pub timezone: Option<String>,
+    /// Rounds decimals to this many decimal places.
+    #[tsify(optional)]
+    pub decimal_places: Option<i8>,
+}
+
+/// The values of a row, in schema order.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)]
+pub struct RowValues(#[tsify(type = "unknown[]")] pub Vec<Value>);
+
+/// A single value. Nested values become arrays and objects, and dates and timestamps become
+/// ISO 8601 strings.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)]
+pub struct CellValue(#[tsify(type = "unknown")] pub Value);
+
+/// Converts each column of a single-row batch to a JSON value.
+///
+/// Integers that JavaScript can't represent exactly are converted to strings.
+pub fn json_values(batch: &RecordBatch) -> Result<Vec<Value>, String> {
+    // Rename the columns so that duplicate names don't collide in the JSON object.
+    let fields: Vec<_> = (0..batch.num_columns())
+        .map(|i| {
+            let field = batch.schema().field(i).clone();
+            Field::new(i.to_string(), field.data_type().clone(), true)
+        })
+        .collect();
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), batch.columns().to_vec())
+        .map_err(|err| err.to_string())?;
+
+    let mut writer = WriterBuilder::new()
+        .with_explicit_nulls(true)
+        .build::<_, LineDelimited>(vec![]);
+    writer.write(&batch).map_err(|err| err.to_string())?;
+    writer.finish().map_err(|err| err.to_string())?;
+
+    let mut row: serde_json::Map<String, Value> =
+        serde_json::from_slice(&writer.into_inner()).map_err(|err| err.to_string())?;
+    Ok((0..batch.num_columns())
+        .map(|i| js_safe(row.remove(&i.to_string()).unwrap_or(Value::Null)))
+        .collect())
+}
+
+fn js_safe(value: Value) -> Value {
+    match value {
+        Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(int), _) if int.unsigned_abs() > MAX_SAFE_INTEGER => {
+                Value::String(number.to_string())
+            }
+            (None, Some(_)) => Value::String(number.to_string()),
+            _ => Value::Number(number),
+        },
+        Value::Array(values) => Value::Array(values.into_iter().map(js_safe).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name, js_safe(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Formats each value of `array` as text.
+pub fn format_values(array: &ArrayRef, options: &DisplayOptions) -> Result<Vec<String>, String> {
+    let array = prepare(array, options)?;
+    let format_options = FormatOptions::new()
+        .with_display_error(true)
+        .with_null(options.null_text.as_deref().unwrap_or(""))
+        .with_date_format(options.date_format.as_deref())
+        .with_time_format(options.time_format.as_deref())
+        .with_timestamp_format(options.timestamp_format.as_deref())
+        .with_timestamp_tz_format(options.timestamp_tz_format.as_deref());
+    let formatter =
+        ArrayFormatter::try_new(array.as_ref(), &format_options).map_err(|err| err.to_string())?;
+    Ok((0..array.len())
+        .map(|i| formatter.value(i).to_string())
+        .collect())
+}
+
+/// Converts time zones and rescales decimals ahead of formatting.
+fn prepare(array: &ArrayRef, options: &DisplayOptions) -> Result<ArrayRef, String> {
+    let data_type = match (array.data_type(), &options.timezone, options.decimal_places) {
+        (DataType::Timestamp(unit, Some(_)), Some(timezone), _) => {
+            DataType::Timestamp(*unit, Some(timezone.as_str().into()))
+        }
+        (DataType::Decimal128(precision, scale), _, Some(places)) => {
+            let precision = (*precision as i16 - *scale as i16 + places as i16)
+                .clamp(1, DECIMAL128_MAX_PRECISION as i16);
+            DataType::Decimal128(precision as u8, places)
+        }
+        (DataType::Decimal256(precision, scale), _, Some(places)) => {
+            let precision = (*precision as i16 - *scale as i16 + places as i16)
+                .clamp(1, DECIMAL256_MAX_PRECISION as i16);
+            DataType::Decimal256(precision as u8, places)
+        }
+        _ => return Ok(array.clone()),
+    };
+    cast(array, &data_type).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::{
+        Array, Decimal128Array, Int64Array, ListArray, StringArray, TimestampSecondArray,
+    };
+    use datafusion::arrow::datatypes::{Int64Type, TimeUnit};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn converts_rows_to_json() {
+        let list = ListArray::from_iter_primitive::<Int64Type, _, _>(vec![Some(vec![Some(1)])]);
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("a", DataType::Utf8, true),
+            Field::new("big", DataType::Int64, true),
+            Field::new("list", list.data_type().clone(), true),
+            Field::new("when", DataType::Timestamp(TimeUnit::Second, None), true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(vec![1])),
+                Arc::new(StringArray::from(vec![None::<&str>])),
+                Arc::new(Int64Array::from(vec![i64::MAX])),
+                Arc::new(list),
+                Arc::new(TimestampSecondArray::from(vec![0])),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            json_values(&batch).unwrap(),
+            [
+                json!(1),
+                Value::Null,
+                json!(i64::MAX.to_string()),
+                json!([1]),
+                json!("1970-01-01T00:00:00")
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_with_options() {
+        let options = DisplayOptions {
+            null_text: Some("(null)".to_string()),
+            timestamp_tz_format: Some("%Y-%m-%d %H:%M %Z".to_string()),
+            timezone: Some("Australia/Sydney".to_string()),
+            decimal_places: Some(1),
+            ..Default::default()
+        };
+
+        let timestamps: ArrayRef =
+            Arc::new(TimestampSecondArray::from(vec![Some(0), None]).with_timezone("UTC"));
+        assert_eq!(
+            format_values(&timestamps, &options).unwrap(),
+            ["1970-01-01 10:00 +10:00", "(null)"]
+        );
+
+        let decimals: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![12345, -5])
+                .with_precision_and_scale(10, 3)
+                .unwrap(),
+        );
+        assert_eq!(
+            format_values(&decimals, &options).unwrap(),
+            ["12.3", "0.0"]
+        );
+    }
+}
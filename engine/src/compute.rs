@@ -0,0 +1,390 @@
+use datafusion::arrow::array::{
+    Array, ArrayRef, BooleanArray, Datum, RecordBatch, Scalar, StringArray, UInt32Array,
+};
+use datafusion::arrow::compute::kernels::boolean::{
+    and_kleene, is_not_null, is_null, not, or_kleene,
+};
+use datafusion::arrow::compute::kernels::cmp;
+use datafusion::arrow::compute::kernels::comparison::{contains, ilike};
+use datafusion::arrow::compute::{cast, cast_with_options, lexsort_to_indices, CastOptions};
+use datafusion::arrow::compute::{SortColumn, SortOptions};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::arrow::error::ArrowError;
+use datafusion::common::{Column, DFSchema, ScalarValue};
+use datafusion::logical_expr::{binary_expr, cast as cast_expr, lit, Expr, Operator};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::record_set::ColumnRef;
+
+/// A column to sort by.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct SortKey {
+    pub column: ColumnRef,
+    #[tsify(optional)]
+    #[serde(default)]
+    pub descending: bool,
+    /// Whether nulls sort before all other values, rather than after them.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub nulls_first: bool,
+}
+
+/// A condition that rows must meet to be kept by a filter.
+///
+/// Comparison values are given as text and parsed as the column's type, so dates, timestamps and
+/// decimals are written the same way as in a CSV file. Rows where the condition is null, such as
+/// comparisons against null values, are not kept.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase", tag = "op")]
+pub enum Predicate {
+    And {
+        predicates: Vec<Predicate>,
+    },
+    Or {
+        predicates: Vec<Predicate>,
+    },
+    Not {
+        predicate: Box<Predicate>,
+    },
+    IsNull {
+        column: ColumnRef,
+    },
+    IsNotNull {
+        column: ColumnRef,
+    },
+    Eq {
+        column: ColumnRef,
+        value: String,
+    },
+    NotEq {
+        column: ColumnRef,
+        value: String,
+    },
+    Lt {
+        column: ColumnRef,
+        value: String,
+    },
+    LtEq {
+        column: ColumnRef,
+        value: String,
+    },
+    Gt {
+        column: ColumnRef,
+        value: String,
+    },
+    GtEq {
+        column: ColumnRef,
+        value: String,
+    },
+    /// Whether the column, formatted as text, contains `value`.
+    #[serde(rename_all = "camelCase")]
+    Contains {
+        column: ColumnRef,
+        value: String,
+        #[tsify(optional)]
+        #[serde(default)]
+        case_insensitive: bool,
+    },
+}
+
+/// Returns the indices that would sort `batch` by the given keys, in order of priority.
+pub fn sort_indices(batch: &RecordBatch, keys: &[SortKey]) -> Result<UInt32Array, String> {
+    if keys.is_empty() {
+        return Ok((0..batch.num_rows() as u32).collect());
+    }
+    let columns = keys
+        .iter()
+        .map(|key| {
+            Ok(SortColumn {
+                values: batch.column(key.column.index(batch.schema_ref())?).clone(),
+                options: Some(SortOptions {
+                    descending: key.descending,
+                    nulls_first: key.nulls_first,
+                }),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    lexsort_to_indices(&columns, None).map_err(|err| err.to_string())
+}
+
+/// Evaluates `predicate` against each row of `batch`.
+pub fn evaluate(predicate: &Predicate, batch: &RecordBatch) -> Result<BooleanArray, String> {
+    let column = |column: &ColumnRef| -> Result<&ArrayRef, String> {
+        Ok(batch.column(column.index(batch.schema_ref())?))
+    };
+    let result = match predicate {
+        Predicate::And { predicates } => {
+            let mut result = BooleanArray::from(vec![true; batch.num_rows()]);
+            for predicate in predicates {
+                result = and_kleene(&result, &evaluate(predicate, batch)?)
+                    .map_err(|err| err.to_string())?;
+            }
+            Ok(result)
+        }
+        Predicate::Or { predicates } => {
+            let mut result = BooleanArray::from(vec![false; batch.num_rows()]);
+            for predicate in predicates {
+                result = or_kleene(&result, &evaluate(predicate, batch)?)
+                    .map_err(|err| err.to_string())?;
+            }
+            Ok(result)
+        }
+        Predicate::Not { predicate } => not(&evaluate(predicate, batch)?),
+        Predicate::IsNull { column: c } => is_null(column(c)?),
+        Predicate::IsNotNull { column: c } => is_not_null(column(c)?),
+        Predicate::Eq { column: c, value } => return compare(cmp::eq, column(c)?, value),
+        Predicate::NotEq { column: c, value } => return compare(cmp::neq, column(c)?, value),
+        Predicate::Lt { column: c, value } => return compare(cmp::lt, column(c)?, value),
+        Predicate::LtEq { column: c, value } => return compare(cmp::lt_eq, column(c)?, value),
+        Predicate::Gt { column: c, value } => return compare(cmp::gt, column(c)?, value),
+        Predicate::GtEq { column: c, value } => return compare(cmp::gt_eq, column(c)?, value),
+        Predicate::Contains { column: c, value, case_insensitive } => {
+            let array = cast(column(c)?, &DataType::Utf8).map_err(|err| err.to_string())?;
+            if *case_insensitive {
+                let pattern = format!("%{}%", escape_like(value));
+                ilike(&array, &StringArray::new_scalar(pattern))
+            } else {
+                contains(&array, &StringArray::new_scalar(value))
+            }
+        }
+    };
+    result.map_err(|err| err.to_string())
+}
+
+/// Converts `predicate` to an expression over a plan with the given schema, so that it can be
+/// pushed down into file scans.
+pub fn to_expr(predicate: &Predicate, schema: &DFSchema) -> Result<Expr, String> {
+    let column = |column: &ColumnRef| -> Result<(Expr, &DataType), String> {
+        let (qualifier, field) = schema.qualified_field(column.index(schema.as_arrow())?);
+        Ok((
+            Expr::Column(Column::from((qualifier, field))),
+            field.data_type(),
+        ))
+    };
+    let compare = |c: &ColumnRef, op: Operator, value: &str| -> Result<Expr, String> {
+        let (expr, data_type) = column(c)?;
+        let value = parse_value(data_type, value)?;
+        let value = ScalarValue::try_from_array(&value, 0).map_err(|err| err.to_string())?;
+        Ok(binary_expr(expr, op, lit(value)))
+    };
+    let exprs = |predicates: &[Predicate]| {
+        predicates
+            .iter()
+            .map(|predicate| to_expr(predicate, schema))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    Ok(match predicate {
+        Predicate::And { predicates } => exprs(predicates)?
+            .into_iter()
+            .reduce(Expr::and)
+            .unwrap_or_else(|| lit(true)),
+        Predicate::Or { predicates } => exprs(predicates)?
+            .into_iter()
+            .reduce(Expr::or)
+            .unwrap_or_else(|| lit(false)),
+        Predicate::Not { predicate } => !to_expr(predicate, schema)?,
+        Predicate::IsNull { column: c } => column(c)?.0.is_null(),
+        Predicate::IsNotNull { column: c } => column(c)?.0.is_not_null(),
+        Predicate::Eq { column: c, value } => compare(c, Operator::Eq, value)?,
+        Predicate::NotEq { column: c, value } => compare(c, Operator::NotEq, value)?,
+        Predicate::Lt { column: c, value } => compare(c, Operator::Lt, value)?,
+        Predicate::LtEq { column: c, value } => compare(c, Operator::LtEq, value)?,
+        Predicate::Gt { column: c, value } => compare(c, Operator::Gt, value)?,
+        Predicate::GtEq { column: c, value } => compare(c, Operator::GtEq, value)?,
+        Predicate::Contains { column: c, value, case_insensitive } => {
+            let expr = cast_expr(column(c)?.0, DataType::Utf8);
+            let pattern = lit(format!("%{}%", escape_like(value)));
+            if *case_insensitive {
+                expr.ilike(pattern)
+            } else {
+                expr.like(pattern)
+            }
+        }
+    })
+}
+
+type CompareFn = fn(&dyn Datum, &dyn Datum) -> Result<BooleanArray, ArrowError>;
+
+fn compare(op: CompareFn, array: &ArrayRef, value: &str) -> Result<BooleanArray, String> {
+    let value = parse_value(array.data_type(), value)?;
+    op(array, &Scalar::new(value)).map_err(|err| err.to_string())
+}
+
+/// Parses a comparison value as a single-element array of the given type.
+fn parse_value(data_type: &DataType, value: &str) -> Result<ArrayRef, String> {
+    let options = CastOptions { safe: false, ..Default::default() };
+    cast_with_options(&StringArray::from(vec![value]), data_type, &options)
+        .map_err(|_| format!("cannot compare {data_type} with {value:?}"))
+}
+
+/// Escapes the wildcards in `value` so it matches literally in a `LIKE` pattern.
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use std::convert::TryFrom;
+
+    use datafusion::arrow::array::{Date32Array, Int64Array};
+    use datafusion::arrow::datatypes::{Field, Schema};
+
+    use super::*;
+
+    fn batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("date", DataType::Date32, true),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(vec![Some(3), None, Some(1), Some(3)])),
+                Arc::new(StringArray::from(vec![
+                    Some("Apple"),
+                    Some("50%_off"),
+                    None,
+                    Some("banana"),
+                ])),
+                Arc::new(Date32Array::from(vec![
+                    Some(19753),
+                    Some(19754),
+                    None,
+                    Some(0),
+                ])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn name(name: &str) -> ColumnRef {
+        ColumnRef::Name(name.to_string())
+    }
+
+    fn matches(predicate: Predicate) -> Vec<bool> {
+        let mask = evaluate(&predicate, &batch()).unwrap();
+        (0..mask.len())
+            .map(|i| mask.is_valid(i) && mask.value(i))
+            .collect()
+    }
+
+    #[test]
+    fn sorts_by_several_keys() {
+        let key = |column: &str, descending, nulls_first| SortKey {
+            column: name(column),
+            descending,
+            nulls_first,
+        };
+        let indices = sort_indices(
+            &batch(),
+            &[key("id", true, false), key("name", false, false)],
+        );
+        assert_eq!(indices.unwrap().values(), &[0, 3, 2, 1]);
+
+        let indices = sort_indices(&batch(), &[key("id", false, true)]);
+        assert_eq!(indices.unwrap().values(), &[1, 2, 0, 3]);
+    }
+
+    #[test]
+    fn compares_with_parsed_values() {
+        let gt = Predicate::Gt {
+            column: name("date"),
+            value: "2024-01-31".to_string(),
+        };
+        assert_eq!(matches(gt), [false, true, false, false]);
+
+        let eq = Predicate::Eq {
+            column: ColumnRef::Index(0),
+            value: "3".to_string(),
+        };
+        assert_eq!(matches(eq), [true, false, false, true]);
+    }
+
+    #[test]
+    fn rejects_unparseable_values() {
+        let predicate = Predicate::Lt {
+            column: name("id"),
+            value: "ten".to_string(),
+        };
+        assert!(evaluate(&predicate, &batch()).is_err());
+    }
+
+    #[test]
+    fn matches_substrings() {
+        let contains = |value: &str, case_insensitive| Predicate::Contains {
+            column: name("name"),
+            value: value.to_string(),
+            case_insensitive,
+        };
+        assert_eq!(matches(contains("an", false)), [false, false, false, true]);
+        assert_eq!(matches(contains("APP", true)), [true, false, false, false]);
+        assert_eq!(matches(contains("%_", true)), [false, true, false, false]);
+        assert_eq!(matches(contains("_", true)), [false, true, false, false]);
+    }
+
+    #[test]
+    fn converts_to_expressions() {
+        let schema = DFSchema::try_from(batch().schema().as_ref().clone()).unwrap();
+        let predicate = Predicate::And {
+            predicates: vec![
+                Predicate::GtEq {
+                    column: name("date"),
+                    value: "2024-01-31".to_string(),
+                },
+                Predicate::Not {
+                    predicate: Box::new(Predicate::Contains {
+                        column: ColumnRef::Index(1),
+                        value: "50%".to_string(),
+                        case_insensitive: true,
+                    }),
+                },
+            ],
+        };
+        assert_eq!(
+            to_expr(&predicate, &schema).unwrap().to_string(),
+            r#"date >= Date32("2024-01-31") AND CAST(name AS Utf8) NOT ILIKE Utf8("%50\%%")"#
+        );
+
+        let predicate = Predicate::Eq {
+            column: name("id"),
+            value: "ten".to_string(),
+        };
+        assert!(to_expr(&predicate, &schema).is_err());
+    }
+
+    #[test]
+    fn combines_predicates() {
+        let id_is_3 = Predicate::Eq {
+            column: name("id"),
+            value: "3".to_string(),
+        };
+        let not_null = Predicate::IsNotNull { column: name("name") };
+        let and = Predicate::And {
+            predicates: vec![id_is_3.clone(), not_null],
+        };
+        assert_eq!(matches(and), [true, false, false, true]);
+
+        // Rows where `id` is null are dropped by both the predicate and its negation.
+        let not = Predicate::Not { predicate: Box::new(id_is_3.clone()) };
+        assert_eq!(matches(not), [false, false, true, false]);
+
+        let or = Predicate::Or {
+            predicates: vec![id_is_3, Predicate::IsNull { column: name("id") }],
+        };
+        assert_eq!(matches(or), [true, true, false, true]);
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::cell::DisplayOptions;
+
+/// The text format of a copied range.
+#[derive(Tsify, Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub enum CopyFormat {
+    /// Tab-separated values, as pasted into spreadsheets such as Excel.
+    Tsv,
+    Csv,
+    Html,
+    Markdown,
+}
+
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyOptions {
+    pub format: CopyFormat,
+    /// Whether to include a row of column names. Defaults to `true`, and Markdown tables always
+    /// include one.
+    #[tsify(optional)]
+    pub include_headers: Option<bool>,
+    #[tsify(optional)]
+    pub display: Option<DisplayOptions>,
+}
+
+/// A column of formatted values.
+pub struct CopyColumn {
+    pub name: String,
+    /// Whether the column holds numbers, which are right-aligned in HTML and Markdown tables.
+    pub numeric: bool,
+    pub values: Vec<String>,
+}
+
+/// Renders columns of equal length as a table in the given format.
+pub fn render(columns: &[CopyColumn], format: CopyFormat, include_headers: bool) -> String {
+    let num_rows = columns.first().map_or(0, |column| column.values.len());
+    let rows = (0..num_rows).map(|row| columns.iter().map(move |column| &column.values[row][..]));
+    let headers = columns.iter().map(|column| &column.name[..]);
+
+    let mut output = String::new();
+    match format {
+        CopyFormat::Tsv | CopyFormat::Csv => {
+            let delimiter = if format == CopyFormat::Tsv { '\t' } else { ',' };
+            let mut write_line = |fields: &mut dyn Iterator<Item = &str>| {
+                for (i, field) in fields.enumerate() {
+                    if i > 0 {
+                        output.push(delimiter);
+                    }
+                    write_delimited(&mut output, field, delimiter);
+                }
+                output.push('\n');
+            };
+            if include_headers {
+                write_line(&mut headers.clone());
+            }
+            for mut row in rows {
+                write_line(&mut row);
+            }
+        }
+        CopyFormat::Html => {
+            let align = |column: &CopyColumn| {
+                if column.numeric {
+                    " style=\"text-align: right\""
+                } else {
+                    ""
+                }
+            };
+            output.push_str("<table>\n");
+            if include_headers {
+                output.push_str("<thead>\n<tr>");
+                for column in columns {
+                    let _ = write!(
+                        output,
+                        "<th{}>{}</th>",
+                        align(column),
+                        escape_html(&column.name)
+                    );
+                }
+                output.push_str("</tr>\n</thead>\n");
+            }
+            output.push_str("<tbody>\n");
+            for row in rows {
+                output.push_str("<tr>");
+                for (column, value) in columns.iter().zip(row) {
+                    let _ = write!(output, "<td{}>{}</td>", align(column), escape_html(value));
+                }
+                output.push_str("</tr>\n");
+            }
+            output.push_str("</tbody>\n</table>\n");
+        }
+        CopyFormat::Markdown => {
+            let mut write_line = |fields: &mut dyn Iterator<Item = &str>| {
+                output.push('|');
+                for field in fields {
+                    let _ = write!(output, " {} |", escape_markdown(field));
+                }
+                output.push('\n');
+            };
+            write_line(&mut headers.clone());
+            let mut separators = columns
+                .iter()
+                .map(|column| if column.numeric { "---:" } else { "---" });
+            write_line(&mut separators);
+            for mut row in rows {
+                write_line(&mut row);
+            }
+        }
+    }
+    output
+}
+
+/// Writes a field, quoting it if it contains the delimiter, a quote or a line break.
+fn write_delimited(output: &mut String, field: &str, delimiter: char) {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        output.push('"');
+        output.push_str(&field.replace('"', "\"\""));
+        output.push('"');
+    } else {
+        output.push_str(field);
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            '\n' => escaped.push_str("<br>"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_markdown(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<CopyColumn> {
+        vec![
+            CopyColumn {
+                name: "name".to_string(),
+                numeric: false,
+                values: vec!["a, \"b\"".to_string(), "x|y\tz".to_string()],
+            },
+            CopyColumn {
+                name: "price".to_string(),
+                numeric: true,
+                values: vec!["1.5".to_string(), "<2>".to_string()],
+            },
+        ]
+    }
+
+    #[test]
+    fn renders_tsv() {
+        assert_eq!(
+            render(&columns(), CopyFormat::Tsv, true),
+            "name\tprice\n\"a, \"\"b\"\"\"\t1.5\n\"x|y\tz\"\t<2>\n"
+        );
+        assert_eq!(
+            render(&columns(), CopyFormat::Tsv, false),
+            "\"a, \"\"b\"\"\"\t1.5\n\"x|y\tz\"\t<2>\n"
+        );
+    }
+
+    #[test]
+    fn renders_csv() {
+        assert_eq!(
+            render(&columns(), CopyFormat::Csv, true),
+            "name,price\n\"a, \"\"b\"\"\",1.5\nx|y\tz,<2>\n"
+        );
+    }
+
+    #[test]
+    fn renders_html() {
+        assert_eq!(
+            render(&columns(), CopyFormat::Html, true),
+            "<table>\n\
+             <thead>\n<tr><th>name</th><th style=\"text-align: right\">price</th></tr>\n</thead>\n\
+             <tbody>\n\
+             <tr><td>a, &quot;b&quot;</td><td style=\"text-align: right\">1.5</td></tr>\n\
+             <tr><td>x|y\tz</td><td style=\"text-align: right\">&lt;2&gt;</td></tr>\n\
+             </tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn renders_markdown() {
+        assert_eq!(
+            render(&columns(), CopyFormat::Markdown, false),
+            "| name | price |\n| --- | ---: |\n| a, \"b\" | 1.5 |\n| x\\|y\tz | <2> |\n"
+        );
+    }
+}
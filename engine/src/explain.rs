@@ -0,0 +1,235 @@
+use datafusion::logical_expr::LogicalPlan;
+use datafusion::physical_plan::display::DisplayableExecutionPlan;
+use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::metrics::MetricValue;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::sorts::sort::SortExec;
+use datafusion::physical_plan::{displayable, ExecutionPlan};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+/// How a plan is executed, at each stage of planning.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct Explanation {
+    /// The plan as it was built.
+    pub logical: ExplainedPlan,
+    /// The plan after the optimizer rewrote it, such as by pushing filters and projections
+    /// into the scan.
+    pub optimized: ExplainedPlan,
+    /// The operators that execute the optimized plan.
+    pub physical: ExplainedPlan,
+    /// Whether the plan was executed, so that its physical nodes have actual rows and times.
+    pub analyzed: bool,
+}
+
+/// One stage of a plan, as the indented text DataFusion prints and as a tree of nodes.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainedPlan {
+    pub text: String,
+    pub root: PlanNode,
+}
+
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanNode {
+    /// The kind of node, such as `Filter` in logical plans or `FilterExec` in physical ones.
+    pub node_type: String,
+    /// The node as a single line of the plan's text.
+    pub description: String,
+    /// The expressions the node evaluates, such as its predicate or projected columns.
+    pub expressions: Vec<String>,
+    /// The number of rows the planner expects the node to output, if it has statistics.
+    #[tsify(optional)]
+    pub estimated_rows: Option<usize>,
+    /// The number of rows the node output when the plan was analyzed.
+    #[tsify(optional)]
+    pub output_rows: Option<usize>,
+    /// The CPU time, in nanoseconds, that the node spent when the plan was analyzed, summed
+    /// over its partitions.
+    #[tsify(optional)]
+    pub elapsed_compute_nanos: Option<usize>,
+    /// The bytes the node read from its files when the plan was analyzed. Only reported by
+    /// Parquet scans.
+    #[tsify(optional)]
+    pub bytes_scanned: Option<usize>,
+    pub children: Vec<PlanNode>,
+}
+
+/// Describes a logical plan, with the schema of each node in its text if `verbose`.
+pub fn explain_logical(plan: &LogicalPlan, verbose: bool) -> ExplainedPlan {
+    let text = if verbose {
+        plan.display_indent_schema().to_string()
+    } else {
+        plan.display_indent().to_string()
+    };
+    ExplainedPlan { text, root: logical_node(plan) }
+}
+
+/// Describes a physical plan, with the metrics of each node in its text if it was executed.
+pub fn explain_physical(plan: &dyn ExecutionPlan, verbose: bool, analyzed: bool) -> ExplainedPlan {
+    let display = if analyzed {
+        DisplayableExecutionPlan::with_metrics(plan)
+    } else {
+        displayable(plan)
+    };
+    let text = display.set_show_schema(verbose).indent(verbose).to_string();
+    ExplainedPlan { text, root: physical_node(plan) }
+}
+
+fn logical_node(plan: &LogicalPlan) -> PlanNode {
+    let description = plan.display().to_string();
+    let node_type = match description.split_once(':') {
+        Some((node_type, _)) => node_type.to_string(),
+        None => description.clone(),
+    };
+    PlanNode {
+        node_type,
+        description,
+        expressions: plan.expressions().iter().map(ToString::to_string).collect(),
+        estimated_rows: None,
+        output_rows: None,
+        elapsed_compute_nanos: None,
+        bytes_scanned: None,
+        children: plan.inputs().into_iter().map(logical_node).collect(),
+    }
+}
+
+fn physical_node(plan: &dyn ExecutionPlan) -> PlanNode {
+    let estimated_rows = plan
+        .partition_statistics(None)
+        .ok()
+        .and_then(|statistics| statistics.num_rows.get_value().copied());
+    let metrics = plan.metrics();
+    let bytes_scanned =
+        metrics
+            .as_ref()
+            .and_then(|metrics| match metrics.sum_by_name("bytes_scanned") {
+                Some(MetricValue::Count { count, .. }) => Some(count.value()),
+                _ => None,
+            });
+    PlanNode {
+        node_type: plan.name().to_string(),
+        description: displayable(plan)
+            .one_line()
+            .to_string()
+            .trim_end()
+            .to_string(),
+        expressions: physical_expressions(plan),
+        estimated_rows,
+        output_rows: metrics.as_ref().and_then(|metrics| metrics.output_rows()),
+        elapsed_compute_nanos: metrics
+            .as_ref()
+            .and_then(|metrics| metrics.elapsed_compute()),
+        bytes_scanned,
+        children: plan
+            .children()
+            .into_iter()
+            .map(|child| physical_node(child.as_ref()))
+            .collect(),
+    }
+}
+
+/// Physical plans have no common way to list their expressions, so they are only listed for
+/// the nodes that the plans built by [`crate::plan::Plan`] commonly have.
+fn physical_expressions(plan: &dyn ExecutionPlan) -> Vec<String> {
+    let any = plan.as_any();
+    if let Some(filter) = any.downcast_ref::<FilterExec>() {
+        vec![filter.predicate().to_string()]
+    } else if let Some(projection) = any.downcast_ref::<ProjectionExec>() {
+        projection.expr().iter().map(ToString::to_string).collect()
+    } else if let Some(sort) = any.downcast_ref::<SortExec>() {
+        sort.expr().iter().map(ToString::to_string).collect()
+    } else {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Int64Array, RecordBatch, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::physical_plan::collect;
+    use datafusion::prelude::*;
+    use futures::executor::block_on;
+
+    use super::*;
+
+    fn data_frame() -> DataFrame {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from_iter_values(0..10)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..10).map(|i| format!("row {i}")),
+                )),
+            ],
+        )
+        .unwrap();
+        let ctx = SessionContext::new();
+        ctx.read_batch(batch)
+            .unwrap()
+            .filter(col("id").gt(lit(6i64)))
+            .unwrap()
+            .select_columns(&["name"])
+            .unwrap()
+    }
+
+    fn find<'a>(node: &'a PlanNode, node_type: &str) -> Option<&'a PlanNode> {
+        if node.node_type == node_type {
+            return Some(node);
+        }
+        node.children
+            .iter()
+            .find_map(|child| find(child, node_type))
+    }
+
+    #[test]
+    fn explains_logical_plans() {
+        let plan = data_frame().into_unoptimized_plan();
+        let explained = explain_logical(&plan, false);
+        assert_eq!(explained.root.node_type, "Projection");
+        assert_eq!(explained.root.expressions, ["?table?.name"]);
+        let filter = &explained.root.children[0];
+        assert_eq!(filter.node_type, "Filter");
+        assert_eq!(filter.expressions, ["?table?.id > Int64(6)"]);
+        assert!(explained
+            .text
+            .starts_with("Projection: ?table?.name\n  Filter:"));
+
+        let verbose = explain_logical(&plan, true);
+        assert!(verbose
+            .text
+            .starts_with("Projection: ?table?.name [name:Utf8]"));
+        assert_eq!(verbose.root, explained.root);
+    }
+
+    #[test]
+    fn explains_executed_physical_plans() {
+        let plan = block_on(data_frame().create_physical_plan()).unwrap();
+        let explained = explain_physical(plan.as_ref(), false, false);
+        let filter = find(&explained.root, "FilterExec").unwrap();
+        assert_eq!(filter.expressions, ["id@0 > 6"]);
+        assert_eq!(filter.output_rows, None);
+        let scan = find(&explained.root, "DataSourceExec").unwrap();
+        assert_eq!(scan.estimated_rows, Some(10));
+
+        let ctx = SessionContext::new();
+        block_on(collect(plan.clone(), ctx.task_ctx())).unwrap();
+        let analyzed = explain_physical(plan.as_ref(), false, true);
+        let filter = find(&analyzed.root, "FilterExec").unwrap();
+        assert_eq!(filter.output_rows, Some(3));
+        assert!(filter.elapsed_compute_nanos.is_some());
+        assert!(analyzed.text.contains("output_rows=3"));
+    }
+}
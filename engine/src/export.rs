@@ -0,0 +1,290 @@
+use std::convert::TryFrom;
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::ipc::writer::FileWriter;
+use datafusion::arrow::json::LineDelimitedWriter;
+use datafusion::parquet::arrow::ArrowWriter;
+use datafusion::parquet::basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel};
+use datafusion::parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+/// The file format that results are exported to.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase", tag = "format")]
+pub enum ExportFormat {
+    #[serde(rename_all = "camelCase")]
+    Csv {
+        /// The field delimiter, which must be a single ASCII character. Defaults to `,`.
+        #[tsify(optional)]
+        delimiter: Option<char>,
+        /// Whether to write a header row of column names. Defaults to `true`.
+        #[tsify(optional)]
+        has_headers: Option<bool>,
+        /// A chrono format string for date columns, such as `%d/%m/%Y`.
+        #[tsify(optional)]
+        date_format: Option<String>,
+        /// A chrono format string for timestamp columns, such as `%Y-%m-%d %H:%M:%S`.
+        #[tsify(optional)]
+        timestamp_format: Option<String>,
+    },
+    JsonLines,
+    #[serde(rename_all = "camelCase")]
+    Parquet {
+        /// Defaults to ZSTD at its default level.
+        #[tsify(optional)]
+        compression: Option<ParquetCompression>,
+    },
+    ArrowIpc,
+}
+
+impl ExportFormat {
+    /// The MIME type of files in this format.
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv { .. } => "text/csv",
+            ExportFormat::JsonLines => "application/x-ndjson",
+            ExportFormat::Parquet { .. } => "application/vnd.apache.parquet",
+            ExportFormat::ArrowIpc => "application/vnd.apache.arrow.file",
+        }
+    }
+}
+
+/// The codec used to compress the pages of a Parquet file.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase", tag = "codec")]
+pub enum ParquetCompression {
+    Uncompressed,
+    Snappy,
+    Gzip {
+        #[tsify(optional)]
+        level: Option<u32>,
+    },
+    Brotli {
+        #[tsify(optional)]
+        level: Option<u32>,
+    },
+    Lz4,
+    Zstd {
+        #[tsify(optional)]
+        level: Option<i32>,
+    },
+}
+
+impl ParquetCompression {
+    fn to_parquet(&self) -> Result<Compression, String> {
+        let err = |err: datafusion::parquet::errors::ParquetError| err.to_string();
+        Ok(match self {
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Gzip { level } => Compression::GZIP(match level {
+                Some(level) => GzipLevel::try_new(*level).map_err(err)?,
+                None => GzipLevel::default(),
+            }),
+            ParquetCompression::Brotli { level } => Compression::BROTLI(match level {
+                Some(level) => BrotliLevel::try_new(*level).map_err(err)?,
+                None => BrotliLevel::default(),
+            }),
+            ParquetCompression::Lz4 => Compression::LZ4_RAW,
+            ParquetCompression::Zstd { level } => Compression::ZSTD(match level {
+                Some(level) => ZstdLevel::try_new(*level).map_err(err)?,
+                None => ZstdLevel::default(),
+            }),
+        })
+    }
+}
+
+/// Writes record batches to an in-memory file, one batch at a time.
+pub struct Exporter {
+    schema: SchemaRef,
+    writer: Writer,
+    written: bool,
+}
+
+enum Writer {
+    Csv(Box<datafusion::arrow::csv::Writer<Vec<u8>>>),
+    JsonLines(LineDelimitedWriter<Vec<u8>>),
+    Parquet(ArrowWriter<Vec<u8>>),
+    ArrowIpc(FileWriter<Vec<u8>>),
+}
+
+impl Exporter {
+    pub fn try_new(schema: SchemaRef, format: &ExportFormat) -> Result<Self, String> {
+        let writer = match format {
+            ExportFormat::Csv {
+                delimiter,
+                has_headers,
+                date_format,
+                timestamp_format,
+            } => {
+                let mut builder = datafusion::arrow::csv::WriterBuilder::new()
+                    .with_header(has_headers.unwrap_or(true));
+                if let Some(delimiter) = delimiter {
+                    let delimiter = u8::try_from(*delimiter)
+                        .ok()
+                        .filter(u8::is_ascii)
+                        .ok_or_else(|| format!("invalid CSV delimiter: {delimiter:?}"))?;
+                    builder = builder.with_delimiter(delimiter);
+                }
+                if let Some(format) = date_format {
+                    builder = builder.with_date_format(format.clone());
+                }
+                if let Some(format) = timestamp_format {
+                    builder = builder
+                        .with_datetime_format(format.clone())
+                        .with_timestamp_format(format.clone());
+                }
+                Writer::Csv(Box::new(builder.build(vec![])))
+            }
+            ExportFormat::JsonLines => Writer::JsonLines(LineDelimitedWriter::new(vec![])),
+            ExportFormat::Parquet { compression } => {
+                let compression = match compression {
+                    Some(compression) => compression.to_parquet()?,
+                    None => Compression::ZSTD(ZstdLevel::default()),
+                };
+                let props = WriterProperties::builder()
+                    .set_compression(compression)
+                    .build();
+                let writer = ArrowWriter::try_new(vec![], schema.clone(), Some(props))
+                    .map_err(|err| err.to_string())?;
+                Writer::Parquet(writer)
+            }
+            ExportFormat::ArrowIpc => {
+                let writer = FileWriter::try_new(vec![], &schema).map_err(|err| err.to_string())?;
+                Writer::ArrowIpc(writer)
+            }
+        };
+        Ok(Self { schema, writer, written: false })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<(), String> {
+        self.written = true;
+        match &mut self.writer {
+            Writer::Csv(writer) => writer.write(batch),
+            Writer::JsonLines(writer) => writer.write(batch),
+            Writer::Parquet(writer) => writer.write(batch).map_err(Into::into),
+            Writer::ArrowIpc(writer) => writer.write(batch),
+        }
+        .map_err(|err| err.to_string())
+    }
+
+    /// Finishes the file and returns its contents.
+    pub fn finish(mut self) -> Result<Vec<u8>, String> {
+        if !self.written {
+            // Ensures CSV files still get a header row when there are no results.
+            self.write(&RecordBatch::new_empty(self.schema.clone()))?;
+        }
+        match self.writer {
+            Writer::Csv(writer) => Ok(writer.into_inner()),
+            Writer::JsonLines(mut writer) => {
+                writer.finish().map_err(|err| err.to_string())?;
+                Ok(writer.into_inner())
+            }
+            Writer::Parquet(writer) => writer.into_inner().map_err(|err| err.to_string()),
+            Writer::ArrowIpc(mut writer) => {
+                writer.finish().map_err(|err| err.to_string())?;
+                writer.into_inner().map_err(|err| err.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Date32Array, Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::ipc::reader::FileReader;
+
+    use super::*;
+
+    fn batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("date", DataType::Date32, true),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+                Arc::new(Date32Array::from(vec![Some(19753), None])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn export(batches: &[RecordBatch], format: ExportFormat) -> Vec<u8> {
+        let mut exporter = Exporter::try_new(batch().schema(), &format).unwrap();
+        for batch in batches {
+            exporter.write(batch).unwrap();
+        }
+        exporter.finish().unwrap()
+    }
+
+    fn csv(delimiter: Option<char>, has_headers: Option<bool>) -> ExportFormat {
+        ExportFormat::Csv {
+            delimiter,
+            has_headers,
+            date_format: Some("%d/%m/%Y".to_string()),
+            timestamp_format: None,
+        }
+    }
+
+    #[test]
+    fn writes_csv_with_options() {
+        let output = export(&[batch()], csv(Some(';'), None));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id;name;date\n1;a;31/01/2024\n2;;\n"
+        );
+
+        let output = export(&[batch()], csv(None, Some(false)));
+        assert_eq!(String::from_utf8(output).unwrap(), "1,a,31/01/2024\n2,,\n");
+    }
+
+    #[test]
+    fn writes_csv_header_without_rows() {
+        let output = export(&[], csv(None, None));
+        assert_eq!(String::from_utf8(output).unwrap(), "id,name,date\n");
+    }
+
+    #[test]
+    fn rejects_invalid_delimiters() {
+        let schema = batch().schema();
+        assert!(Exporter::try_new(schema, &csv(Some('é'), None)).is_err());
+    }
+
+    #[test]
+    fn writes_json_lines() {
+        let output = export(&[batch()], ExportFormat::JsonLines);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"id\":1,\"name\":\"a\",\"date\":\"2024-01-31\"}\n{\"id\":2}\n"
+        );
+    }
+
+    #[test]
+    fn writes_parquet() {
+        let compression = Some(ParquetCompression::Gzip { level: Some(9) });
+        let output = export(&[batch()], ExportFormat::Parquet { compression });
+        assert!(output.starts_with(b"PAR1") && output.ends_with(b"PAR1"));
+
+        let compression = Some(ParquetCompression::Zstd { level: Some(100) });
+        let format = ExportFormat::Parquet { compression };
+        assert!(Exporter::try_new(batch().schema(), &format).is_err());
+    }
+
+    #[test]
+    fn writes_arrow_ipc_files() {
+        let output = export(&[batch(), batch()], ExportFormat::ArrowIpc);
+        let reader = FileReader::try_new(std::io::Cursor::new(output), None).unwrap();
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(batches, [batch(), batch()]);
+    }
+}
@@ -11,11 +11,36 @@ pub enum FileFormat {
         flatten_top_level_arrays: bool,
         #[tsify(optional)]
         single_field: Option<String>,
+        /// Whether values may span or share lines, as in pretty-printed JSON and JSON text
+        /// sequences, so that the file must be split into records before it is scanned.
+        #[tsify(optional)]
+        #[serde(default)]
+        concatenated: bool,
     },
     #[serde(rename_all = "camelCase")]
     Csv {
         encoding: String,
         has_headers: bool,
     },
-    Parquet,
+    /// Parquet files are read in ranges, so that row groups and pages that can't match the plan's
+    /// filters are skipped.
+    #[serde(rename_all = "camelCase")]
+    Parquet {
+        /// Whether filters are evaluated while decoding, so that the other columns of rows that
+        /// don't match are never decoded. Defaults to `true`.
+        #[tsify(optional)]
+        pushdown_filters: Option<bool>,
+        /// Whether to read the page index, if present, to skip pages that can't match the
+        /// filters. Defaults to `true`.
+        #[tsify(optional)]
+        page_index: Option<bool>,
+        /// Whether to check bloom filters, if present, to skip row groups that can't match
+        /// equality filters. Defaults to `true`.
+        #[tsify(optional)]
+        bloom_filters: Option<bool>,
+        /// How many bytes to read from the end of the file when reading its metadata, which
+        /// saves a request when the metadata fits. Defaults to 512 KiB.
+        #[tsify(optional)]
+        metadata_size_hint: Option<usize>,
+    },
 }
@@ -0,0 +1,236 @@
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+
+use crate::file_format::FileFormat;
+use crate::json_infer::{JsonDetector, JsonKind, JsonSummary, JsonValidator};
+use crate::parquet::ParquetMetadata;
+use crate::schema_report::SchemaReport;
+use crate::source::InputSource;
+use crate::utils::chunk_ranges;
+
+/// The size of the chunks that files are streamed through detectors in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Infers a file's format from its extension and, for text formats, its contents.
+pub async fn infer_format(source: &dyn InputSource) -> Result<FileFormat, String> {
+    let filename = source.name().unwrap_or_default();
+    let ext = std::path::Path::new(&filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .ok_or("no file extension")?;
+
+    Ok(match ext {
+        "csv" => {
+            let encoding = infer_encoding(source).await?;
+            FileFormat::Csv { encoding, has_headers: true }
+        }
+        "json" | "jsonl" => {
+            let kind = infer_json_kind(source, false).await?;
+            FileFormat::Json {
+                flatten_top_level_arrays: kind == JsonKind::JsonArray,
+                single_field: (kind == JsonKind::JsonValues).then(|| "value".to_string()),
+                concatenated: matches!(kind, JsonKind::ConcatenatedJson | JsonKind::JsonSeq),
+            }
+        }
+        "parquet" => FileFormat::Parquet {
+            pushdown_filters: None,
+            page_index: None,
+            bloom_filters: None,
+            metadata_size_hint: None,
+        },
+        ext => Err(format!("unknown file extension: {ext}"))?,
+    })
+}
+
+/// Infers the schema of a file, and optionally explains how each column's type was inferred.
+pub async fn infer_schema(
+    source: &dyn InputSource,
+    format: &FileFormat,
+    max_records: Option<usize>,
+    report: bool,
+) -> Result<(SchemaRef, Option<SchemaReport>), String> {
+    use datafusion::arrow::csv::reader::Format as CsvFormat;
+    use datafusion::arrow::json::reader::{infer_json_schema_with_options, InferJsonSchemaOptions};
+    use datafusion::parquet::arrow::ParquetRecordBatchStreamBuilder;
+
+    let bytes = source.read().await?;
+    let mut bytes = match format {
+        FileFormat::Json { concatenated: true, .. } => {
+            crate::json_normalize::to_json_lines(&bytes)?
+        }
+        _ => bytes,
+    };
+
+    let schema = match format.clone() {
+        FileFormat::Csv { has_headers, .. } => {
+            let (schema, _) = CsvFormat::default()
+                .with_header(has_headers)
+                .infer_schema(&bytes[..], max_records)
+                .map_err(|err| err.to_string())?;
+            Arc::new(schema)
+        }
+        FileFormat::Json {
+            flatten_top_level_arrays, single_field, ..
+        } => {
+            let options = InferJsonSchemaOptions {
+                max_read_records: max_records,
+                flatten_top_level_arrays,
+                single_field,
+                ..Default::default()
+            };
+            let (schema, _) = infer_json_schema_with_options(&bytes[..], options)
+                .map_err(|err| err.to_string())?;
+            Arc::new(schema)
+        }
+        FileFormat::Parquet { .. } => {
+            // The reader has to own the bytes, so they are only copied if the report needs them.
+            let bytes = if report {
+                bytes.clone()
+            } else {
+                std::mem::take(&mut bytes)
+            };
+            let reader = ParquetRecordBatchStreamBuilder::new(std::io::Cursor::new(bytes))
+                .await
+                .map_err(|err| err.to_string())?;
+            Arc::clone(reader.schema())
+        }
+    };
+    let report = if report {
+        Some(crate::schema_report::build_report(bytes, format, &schema, max_records).await?)
+    } else {
+        None
+    };
+
+    Ok((schema, report))
+}
+
+/// Reads the metadata in the footer of a Parquet file, without reading any of its data.
+pub async fn inspect_parquet(source: &dyn InputSource) -> Result<ParquetMetadata, String> {
+    use crate::parquet::{decode_metadata, metadata_length, FOOTER_SIZE};
+
+    let size = source.size();
+    let footer_start = size
+        .checked_sub(FOOTER_SIZE as u64)
+        .ok_or("file is too small to be a Parquet file")?;
+    let footer = source.read_range(footer_start..size).await?;
+    let metadata_start = footer_start
+        .checked_sub(metadata_length(&footer)? as u64)
+        .ok_or("Parquet metadata is larger than the file")?;
+    decode_metadata(&source.read_range(metadata_start..footer_start).await?)
+}
+
+pub async fn infer_encoding(source: &dyn InputSource) -> Result<String, String> {
+    let mut detector = chardet::UniversalDetector::new();
+    for (range, _) in chunk_ranges(source.size(), CHUNK_SIZE) {
+        detector.feed(&source.read_range(range).await?);
+    }
+    Ok(detector.close().0)
+}
+
+pub async fn infer_json_kind(source: &dyn InputSource, strict: bool) -> Result<JsonKind, String> {
+    let mut detector = match strict {
+        true => JsonDetector::strict(),
+        false => JsonDetector::new(),
+    };
+    for (range, _) in chunk_ranges(source.size(), CHUNK_SIZE) {
+        let bytes = source.read_range(range).await?;
+        if detector.feed(&bytes).map_err(|err| err.to_string())? {
+            break;
+        }
+    }
+    if !strict {
+        return detector.finish().map_err(|err| err.to_string());
+    }
+
+    let (kind, summary) = detector
+        .finish_with_summary()
+        .map_err(|err| err.to_string())?;
+    // The validator tolerates comments and trailing commas, but DataFusion can't read them.
+    if let Some(summary) = summary {
+        let count = |n: u64, what: &str| match n {
+            0 => None,
+            1 => Some(format!("1 {what}")),
+            n => Some(format!("{n} {what}s")),
+        };
+        let found: Vec<_> = vec![
+            count(summary.comments, "comment"),
+            count(summary.trailing_commas, "trailing comma"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !found.is_empty() {
+            return Err(format!("invalid JSON: found {}", found.join(" and ")));
+        }
+    }
+    Ok(kind)
+}
+
+pub async fn validate_json(source: &dyn InputSource) -> Result<JsonSummary, String> {
+    let mut validator = JsonValidator::new();
+    for (range, _) in chunk_ranges(source.size(), CHUNK_SIZE) {
+        let bytes = source.read_range(range).await?;
+        if validator.feed(&bytes).is_err() {
+            return Ok(validator.summary());
+        }
+    }
+    // Syntax errors are reported in the summary rather than thrown.
+    let _ = validator.finish();
+    Ok(validator.summary())
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::source::BytesSource;
+
+    #[test]
+    fn infers_formats_from_names_and_contents() {
+        let source = BytesSource::new(&b"[{\"a\": 1}, {\"a\": 2}]"[..]).with_name("rows.json");
+        let format = block_on(infer_format(&source)).unwrap();
+        assert!(matches!(
+            format,
+            FileFormat::Json {
+                flatten_top_level_arrays: true,
+                concatenated: false,
+                ..
+            }
+        ));
+
+        let source = BytesSource::new(&b"a,b\n1,2\n"[..]).with_name("rows.csv");
+        let format = block_on(infer_format(&source)).unwrap();
+        assert!(matches!(format, FileFormat::Csv { has_headers: true, .. }));
+
+        let source = BytesSource::new(&b""[..]).with_name("rows");
+        assert!(block_on(infer_format(&source)).is_err());
+    }
+
+    #[test]
+    fn strict_json_kind_rejects_comments_and_trailing_commas() {
+        let source = BytesSource::new(&b"// rows\n[{\"a\": 1},]"[..]);
+        assert_eq!(
+            block_on(infer_json_kind(&source, true)),
+            Err("invalid JSON: found 1 comment and 1 trailing comma".to_string())
+        );
+        assert!(block_on(infer_json_kind(&source, false)).is_ok());
+
+        let source = BytesSource::new(&b"truefalse"[..]);
+        assert!(block_on(infer_json_kind(&source, true)).is_err());
+    }
+
+    #[test]
+    fn infers_csv_schemas() {
+        let source = BytesSource::new(&b"id,name\n1,a\n2,b\n"[..]);
+        let format = FileFormat::Csv {
+            encoding: "ascii".to_string(),
+            has_headers: true,
+        };
+        let (schema, report) = block_on(infer_schema(&source, &format, None, false)).unwrap();
+        let names: Vec<_> = schema.fields().iter().map(|field| field.name()).collect();
+        assert_eq!(names, ["id", "name"]);
+        assert!(report.is_none());
+    }
+}
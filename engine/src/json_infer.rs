@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
 use wasm_bindgen::prelude::*;
 
 /// The detected kind of a JSON input stream.
@@ -11,20 +13,32 @@ pub enum JsonKind {
     /// The file contains one or more top-level non-object JSON values (strings, numbers,
     /// booleans, null, or arrays that are *not* the only value).
     JsonValues,
+    /// The file is a sequence of top-level JSON objects that are not one per line, e.g.
+    /// pretty-printed objects, or objects with no newline between them.
+    ConcatenatedJson,
+    /// The file is an RFC 7464 JSON text sequence, where each value is preceded by an
+    /// ASCII record separator (`0x1E`).
+    JsonSeq,
 }
 
+/// The ASCII record separator that starts each value of a JSON text sequence.
+pub const RECORD_SEPARATOR: u8 = 0x1E;
+
 /// Errors that can be returned by the detector.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DetectorError {
     /// An unexpected byte was encountered (e.g. a second top-level value whose type is
     /// incompatible with the first).
     Incompatible(String),
+    /// The input is not valid JSON. Only returned in strict mode.
+    Syntax(SyntaxError),
 }
 
 impl std::fmt::Display for DetectorError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             DetectorError::Incompatible(msg) => write!(f, "incompatible JSON values: {msg}"),
+            DetectorError::Syntax(err) => write!(f, "invalid JSON: {err}"),
         }
     }
 }
@@ -39,6 +53,15 @@ impl std::error::Error for DetectorError {}
 enum Phase {
     /// Haven't seen any non-whitespace byte yet.
     Initial,
+    /// We are inside the first top-level object, tracking bracket depth.
+    InFirstObject {
+        depth: usize,
+        in_string: bool,
+        escaped: bool,
+    },
+    /// First value was an object that fit on one line; look for what separates it
+    /// from the next value.
+    AfterFirstObject,
     /// We are inside a top-level JSON string (first value).
     InString { escaped: bool },
     /// We are inside the first top-level array, tracking bracket depth.
@@ -58,24 +81,43 @@ enum Phase {
 /// Feed chunks of bytes via [`JsonDetector::feed`].  The method returns
 /// `Ok(Some(kind))` once the kind is known, `Ok(None)` if more data is
 /// needed, or `Err(e)` on an incompatibility error.
+///
+/// By default only the first values are inspected.  A detector created with
+/// [`JsonDetector::strict`] also validates the whole stream with a
+/// [`JsonValidator`].
 pub struct JsonDetector {
     phase: Phase,
+    validator: Option<JsonValidator>,
 }
 
 impl JsonDetector {
     pub fn new() -> Self {
-        Self { phase: Phase::Initial }
+        Self { phase: Phase::Initial, validator: None }
+    }
+
+    /// Creates a detector that validates every byte of the stream, rather
+    /// than stopping as soon as the kind is known.
+    pub fn strict() -> Self {
+        Self {
+            phase: Phase::Initial,
+            validator: Some(JsonValidator::new()),
+        }
     }
 
     /// Feed a chunk of bytes.  Returns:
     /// - `Ok(false)` – need more data; keep feeding
     /// - `Ok(true)`  – kind determined; call [`finish`](Self::finish) to retrieve it
-    /// - `Err(e)`    – incompatible values detected
+    /// - `Err(e)`    – incompatible values detected, or a syntax error in strict mode
+    ///
+    /// In strict mode this never returns `Ok(true)`, as the whole stream must be seen.
     pub fn feed(&mut self, chunk: &[u8]) -> Result<bool, DetectorError> {
+        if let Some(validator) = &mut self.validator {
+            validator.feed(chunk).map_err(DetectorError::Syntax)?;
+        }
         for &b in chunk {
             self.step(b)?;
             if matches!(self.phase, Phase::Done(_)) {
-                return Ok(true);
+                return Ok(self.validator.is_none());
             }
         }
         Ok(false)
@@ -84,7 +126,20 @@ impl JsonDetector {
     /// Signal end-of-stream.  Resolves any ambiguity that required seeing a
     /// second value (e.g. a lone top-level array → `JsonArray`).
     pub fn finish(self) -> Result<JsonKind, DetectorError> {
-        match self.phase {
+        self.finish_with_summary().map(|(kind, _)| kind)
+    }
+
+    /// Like [`finish`](Self::finish), but in strict mode also returns what the
+    /// validator found in the stream.
+    pub fn finish_with_summary(self) -> Result<(JsonKind, Option<JsonSummary>), DetectorError> {
+        let summary = match self.validator {
+            Some(mut validator) => {
+                validator.finish().map_err(DetectorError::Syntax)?;
+                Some(validator.summary())
+            }
+            None => None,
+        };
+        let kind = match self.phase {
             Phase::Done(k) => Ok(k),
             Phase::AfterFirstArray => {
                 // Only one top-level value and it was an array → JsonArray.
@@ -96,7 +151,10 @@ impl JsonDetector {
             }
             Phase::InString { .. } => Ok(JsonKind::JsonValues),
             Phase::InFirstArray { .. } => Ok(JsonKind::JsonArray),
-        }
+            // A single object, or a truncated first line.
+            Phase::InFirstObject { .. } | Phase::AfterFirstObject => Ok(JsonKind::JsonLines),
+        }?;
+        Ok((kind, summary))
     }
 
     fn step(&mut self, b: u8) -> Result<(), DetectorError> {
@@ -105,9 +163,17 @@ impl JsonDetector {
             Phase::Initial => {
                 if b.is_ascii_whitespace() {
                     // skip
+                } else if b == RECORD_SEPARATOR {
+                    // Only JSON text sequences start with a record separator.
+                    self.phase = Phase::Done(JsonKind::JsonSeq);
                 } else if b == b'{' {
-                    // First value is an object → JsonLines immediately.
-                    self.phase = Phase::Done(JsonKind::JsonLines);
+                    // First value is an object.  Track it to see whether it
+                    // spans lines or shares a line with the next value.
+                    self.phase = Phase::InFirstObject {
+                        depth: 1,
+                        in_string: false,
+                        escaped: false,
+                    };
                 } else if b == b'[' {
                     // First value is an array.  Track it to see if there is a
                     // second top-level value afterwards.
@@ -137,6 +203,44 @@ impl JsonDetector {
                 }
             }
 
+            // ---------------------------------------------------------------
+            Phase::InFirstObject { depth, in_string, escaped } => {
+                if *in_string {
+                    if *escaped {
+                        *escaped = false;
+                    } else if b == b'\\' {
+                        *escaped = true;
+                    } else if b == b'"' {
+                        *in_string = false;
+                    }
+                } else {
+                    match b {
+                        b'"' => *in_string = true,
+                        b'{' | b'[' => *depth += 1,
+                        b'}' | b']' => {
+                            *depth -= 1;
+                            if *depth == 0 {
+                                self.phase = Phase::AfterFirstObject;
+                            }
+                        }
+                        // An object split over several lines is pretty-printed.
+                        b'\n' => self.phase = Phase::Done(JsonKind::ConcatenatedJson),
+                        _ => {}
+                    }
+                }
+            }
+
+            // ---------------------------------------------------------------
+            // The first object ended on the line it started on.  A newline
+            // before the next value means JSON Lines.
+            Phase::AfterFirstObject => {
+                if b == b'\n' {
+                    self.phase = Phase::Done(JsonKind::JsonLines);
+                } else if !b.is_ascii_whitespace() {
+                    self.phase = Phase::Done(JsonKind::ConcatenatedJson);
+                }
+            }
+
             // ---------------------------------------------------------------
             Phase::InFirstArray { depth, in_string, escaped } => {
                 if *in_string {
@@ -189,6 +293,7 @@ impl JsonDetector {
                 }
                 // Any other byte (e.g. stray comma) is ignored; real JSON
                 // parsers would reject it but we are only detecting kind.
+                // Strict mode catches it in the validator instead.
             }
 
             // ---------------------------------------------------------------
@@ -206,6 +311,425 @@ impl Default for JsonDetector {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Strict validation
+// ---------------------------------------------------------------------------
+
+/// The first syntax error in a JSON stream.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxError {
+    /// The zero-based byte offset of the offending byte.
+    pub offset: u64,
+    /// The one-based line of the offending byte.
+    pub line: u64,
+    /// The one-based column of the offending byte, counted in characters.
+    pub column: u64,
+    pub message: String,
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { offset, line, column, message } = self;
+        write!(
+            f,
+            "{message} at line {line}, column {column} (byte {offset})"
+        )
+    }
+}
+
+/// What a [`JsonValidator`] found in a JSON stream.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonSummary {
+    /// The number of records: the elements of a lone top-level array, or
+    /// otherwise the number of top-level values.
+    pub records: u64,
+    /// The number of `//` and `/* */` comments, which are not valid JSON.
+    pub comments: u64,
+    /// The number of commas directly before a `]` or `}`, which are not valid JSON.
+    pub trailing_commas: u64,
+    /// The first syntax error, if any.  The counts only cover the input before it.
+    #[tsify(optional)]
+    pub error: Option<SyntaxError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Array,
+    Object,
+}
+
+/// Whether a closing bracket may appear where a value or key is expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Close {
+    /// After `:`, or at the top level.
+    Forbidden,
+    /// Directly after the opening bracket.
+    Empty,
+    /// After a comma, which makes the comma a trailing comma.
+    Trailing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    Backslash,
+    /// Inside `\uXXXX`, with this many hex digits left.
+    Unicode(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Number {
+    Minus,
+    Zero,
+    Integer,
+    Dot,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits,
+}
+
+impl Number {
+    fn next(self, b: u8) -> Option<Self> {
+        use Number::*;
+        match (self, b) {
+            (Minus, b'0') => Some(Zero),
+            (Minus, b'1'..=b'9') => Some(Integer),
+            (Integer, b'0'..=b'9') => Some(Integer),
+            (Zero | Integer, b'.') => Some(Dot),
+            (Dot | Fraction, b'0'..=b'9') => Some(Fraction),
+            (Zero | Integer | Fraction, b'e' | b'E') => Some(Exponent),
+            (Exponent, b'+' | b'-') => Some(ExponentSign),
+            (Exponent | ExponentSign | ExponentDigits, b'0'..=b'9') => Some(ExponentDigits),
+            _ => None,
+        }
+    }
+
+    fn is_complete(self) -> bool {
+        matches!(
+            self,
+            Number::Zero | Number::Integer | Number::Fraction | Number::ExponentDigits
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comment {
+    /// Seen the first `/`.
+    Start,
+    Line,
+    Block,
+    /// Seen a `*` inside a block comment.
+    BlockStar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Value(Close),
+    Key(Close),
+    Colon,
+    AfterValue,
+    String {
+        key: bool,
+        escape: Escape,
+    },
+    Number(Number),
+    /// Inside `true`, `false` or `null`, with these bytes left.
+    Literal(&'static [u8]),
+    Comment(Comment),
+}
+
+/// Streaming JSON tokenizer that checks the syntax of a whole stream.
+///
+/// Accepts any number of top-level values separated by whitespace or record
+/// separators, as in JSON Lines and JSON text sequences. Comments and
+/// trailing commas are tolerated but counted, so that JSON-with-comments can
+/// be told apart from other invalid input.
+pub struct JsonValidator {
+    token: Token,
+    /// The token to return to once the current comment ends.
+    resume: Token,
+    stack: Vec<Container>,
+    offset: u64,
+    line: u64,
+    column: u64,
+    top_level_values: u64,
+    first_is_array: bool,
+    first_array_elements: u64,
+    comments: u64,
+    trailing_commas: u64,
+    /// Whether whitespace, a record separator or a comment followed the last top-level value.
+    separated: bool,
+    /// Whether the last top-level value was an object or array.
+    closed_container: bool,
+    error: Option<SyntaxError>,
+}
+
+impl JsonValidator {
+    pub fn new() -> Self {
+        Self {
+            token: Token::Value(Close::Forbidden),
+            resume: Token::Value(Close::Forbidden),
+            stack: vec![],
+            offset: 0,
+            line: 1,
+            column: 1,
+            top_level_values: 0,
+            first_is_array: false,
+            first_array_elements: 0,
+            comments: 0,
+            trailing_commas: 0,
+            separated: false,
+            closed_container: false,
+            error: None,
+        }
+    }
+
+    /// Feed a chunk of bytes, returning the first syntax error.  Once an
+    /// error is returned, every later call returns the same error.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), SyntaxError> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        for &b in chunk {
+            // Bytes that end a number are processed again in the next state.
+            while !self.step(b).map_err(|err| self.fail(err))? {}
+            self.offset += 1;
+            if b == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if b & 0xC0 != 0x80 {
+                self.column += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Signal end-of-stream, returning an error if a value is incomplete.
+    pub fn finish(&mut self) -> Result<(), SyntaxError> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        match self.token {
+            Token::Number(number) if number.is_complete() => self.token = Token::AfterValue,
+            Token::Comment(Comment::Line) => self.token = self.resume,
+            _ => {}
+        }
+        let complete = matches!(
+            self.token,
+            Token::AfterValue | Token::Value(Close::Forbidden)
+        );
+        if !complete || !self.stack.is_empty() {
+            return Err(self.fail("unexpected end of input"));
+        }
+        Ok(())
+    }
+
+    pub fn summary(&self) -> JsonSummary {
+        let records = if self.top_level_values == 1 && self.first_is_array {
+            self.first_array_elements
+        } else {
+            self.top_level_values
+        };
+        JsonSummary {
+            records,
+            comments: self.comments,
+            trailing_commas: self.trailing_commas,
+            error: self.error.clone(),
+        }
+    }
+
+    fn fail(&mut self, message: &str) -> SyntaxError {
+        let err = SyntaxError {
+            offset: self.offset,
+            line: self.line,
+            column: self.column,
+            message: message.to_string(),
+        };
+        self.error = Some(err.clone());
+        err
+    }
+
+    /// Processes one byte.  Returns `Ok(false)` if the byte was not consumed
+    /// and must be processed again.
+    fn step(&mut self, b: u8) -> Result<bool, &'static str> {
+        let innermost = self.stack.last().copied();
+        let between_values = matches!(self.token, Token::AfterValue) && self.stack.is_empty();
+        match self.token {
+            Token::Value(_) | Token::Key(_) | Token::Colon | Token::AfterValue
+                if b.is_ascii_whitespace() =>
+            {
+                self.separated |= between_values;
+            }
+            // JSON text sequences separate top-level values with record separators.
+            Token::Value(_) | Token::AfterValue
+                if b == RECORD_SEPARATOR && self.stack.is_empty() =>
+            {
+                self.separated |= between_values;
+            }
+            Token::Value(_) | Token::Key(_) | Token::Colon | Token::AfterValue if b == b'/' => {
+                self.separated |= between_values;
+                self.resume = self.token;
+                self.token = Token::Comment(Comment::Start);
+            }
+
+            // ---------------------------------------------------------------
+            Token::Value(close) => match b {
+                b']' if close != Close::Forbidden && innermost == Some(Container::Array) => {
+                    self.close(close)
+                }
+                _ => self.start_value(b)?,
+            },
+
+            // ---------------------------------------------------------------
+            Token::Key(close) => match b {
+                b'"' => self.token = Token::String { key: true, escape: Escape::None },
+                b'}' if close != Close::Forbidden => self.close(close),
+                _ => return Err("expected a string key"),
+            },
+
+            Token::Colon => match b {
+                b':' => self.token = Token::Value(Close::Forbidden),
+                _ => return Err("expected `:`"),
+            },
+
+            // ---------------------------------------------------------------
+            Token::AfterValue => match (b, innermost) {
+                (b',', Some(Container::Array)) => self.token = Token::Value(Close::Trailing),
+                (b',', Some(Container::Object)) => self.token = Token::Key(Close::Trailing),
+                (b']', Some(Container::Array)) | (b'}', Some(Container::Object)) => {
+                    self.close(Close::Forbidden)
+                }
+                (b',', None) => return Err("unexpected `,` between top-level values"),
+                // Objects and arrays may follow each other directly, as in concatenated JSON,
+                // but other values would run together, as in `truefalse`.
+                (_, None)
+                    if self.separated || self.closed_container && matches!(b, b'{' | b'[') =>
+                {
+                    self.start_value(b)?
+                }
+                (_, None) => return Err("expected whitespace between top-level values"),
+                (_, Some(Container::Array)) => return Err("expected `,` or `]`"),
+                (_, Some(Container::Object)) => return Err("expected `,` or `}`"),
+            },
+
+            // ---------------------------------------------------------------
+            Token::String { key, escape } => {
+                let escape = match (escape, b) {
+                    (Escape::None, b'"') => {
+                        self.token = if key { Token::Colon } else { Token::AfterValue };
+                        return Ok(true);
+                    }
+                    (Escape::None, b'\\') => Escape::Backslash,
+                    (Escape::None, 0x00..=0x1F) => return Err("control character in string"),
+                    (Escape::None, _) => Escape::None,
+                    (Escape::Backslash, b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => {
+                        Escape::None
+                    }
+                    (Escape::Backslash, b'u') => Escape::Unicode(4),
+                    (Escape::Backslash, _) => return Err("invalid escape sequence"),
+                    (Escape::Unicode(1), b) if b.is_ascii_hexdigit() => Escape::None,
+                    (Escape::Unicode(n), b) if b.is_ascii_hexdigit() => Escape::Unicode(n - 1),
+                    (Escape::Unicode(_), _) => return Err("invalid unicode escape"),
+                };
+                self.token = Token::String { key, escape };
+            }
+
+            // ---------------------------------------------------------------
+            Token::Number(number) => match number.next(b) {
+                Some(number) => self.token = Token::Number(number),
+                None if number.is_complete() => {
+                    self.token = Token::AfterValue;
+                    return Ok(false);
+                }
+                None => return Err("invalid number"),
+            },
+
+            Token::Literal(rest) => match rest.split_first() {
+                Some((&expected, rest)) if b == expected => {
+                    self.token = if rest.is_empty() {
+                        Token::AfterValue
+                    } else {
+                        Token::Literal(rest)
+                    };
+                }
+                _ => return Err("invalid literal"),
+            },
+
+            // ---------------------------------------------------------------
+            Token::Comment(comment) => {
+                let comment = match (comment, b) {
+                    (Comment::Start, b'/') => Comment::Line,
+                    (Comment::Start, b'*') => Comment::Block,
+                    (Comment::Start, _) => return Err("expected `/` or `*` after `/`"),
+                    (Comment::Line, b'\n') | (Comment::BlockStar, b'/') => {
+                        self.comments += 1;
+                        self.token = self.resume;
+                        return Ok(true);
+                    }
+                    (Comment::Line, _) => Comment::Line,
+                    (Comment::Block | Comment::BlockStar, b'*') => Comment::BlockStar,
+                    (Comment::Block | Comment::BlockStar, _) => Comment::Block,
+                };
+                self.token = Token::Comment(comment);
+            }
+        }
+        Ok(true)
+    }
+
+    fn start_value(&mut self, b: u8) -> Result<(), &'static str> {
+        self.token = match b {
+            b'{' => Token::Key(Close::Empty),
+            b'[' => Token::Value(Close::Empty),
+            b'"' => Token::String { key: false, escape: Escape::None },
+            b'-' => Token::Number(Number::Minus),
+            b'0' => Token::Number(Number::Zero),
+            b'1'..=b'9' => Token::Number(Number::Integer),
+            b't' => Token::Literal(b"rue"),
+            b'f' => Token::Literal(b"alse"),
+            b'n' => Token::Literal(b"ull"),
+            _ => return Err("expected a value"),
+        };
+
+        if self.stack.is_empty() {
+            self.separated = false;
+            self.closed_container = false;
+            self.top_level_values += 1;
+            if self.top_level_values == 1 {
+                self.first_is_array = b == b'[';
+            }
+        } else if self.stack.len() == 1 && self.top_level_values == 1 && self.first_is_array {
+            self.first_array_elements += 1;
+        }
+
+        match b {
+            b'{' => self.stack.push(Container::Object),
+            b'[' => self.stack.push(Container::Array),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn close(&mut self, close: Close) {
+        if close == Close::Trailing {
+            self.trailing_commas += 1;
+        }
+        self.stack.pop();
+        self.closed_container = self.stack.is_empty();
+        self.token = Token::AfterValue;
+    }
+}
+
+impl Default for JsonValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -340,4 +864,144 @@ mod tests {
             Ok(JsonKind::JsonArray)
         );
     }
+
+    // -- Strict mode ---------------------------------------------------------
+
+    fn validate(input: &[u8]) -> JsonSummary {
+        let mut v = JsonValidator::new();
+        if v.feed(input).is_ok() {
+            let _ = v.finish();
+        }
+        v.summary()
+    }
+
+    fn syntax_error(input: &[u8]) -> (u64, u64, u64) {
+        let err = validate(input).error.expect("expected a syntax error");
+        (err.offset, err.line, err.column)
+    }
+
+    #[test]
+    fn strict_counts_records() {
+        assert_eq!(validate(b"{\"a\":1}\n{\"a\":2}\n").records, 2);
+        assert_eq!(validate(b"[{\"a\":[1,2]}, {}, 3]").records, 3);
+        assert_eq!(validate(b"[1]\n[2]").records, 2);
+        assert_eq!(validate(b"  ").records, 0);
+    }
+
+    #[test]
+    fn strict_accepts_valid_json() {
+        let input = br#"{"a": [true, false, null], "b": -1.5e+3, "c": "\u00e9\n", "d": {}}"#;
+        assert_eq!(validate(input).error, None);
+    }
+
+    #[test]
+    fn strict_counts_comments_and_trailing_commas() {
+        let input = b"// header\n{\"a\": [1, 2,], /* note */ \"b\": 2,}\n";
+        let summary = validate(input);
+        assert_eq!(summary.error, None);
+        assert_eq!(summary.comments, 2);
+        assert_eq!(summary.trailing_commas, 2);
+    }
+
+    #[test]
+    fn strict_reports_error_position() {
+        assert_eq!(syntax_error(b"{\"a\":1}\n{\"a\" 2}"), (13, 2, 6));
+        assert_eq!(syntax_error(b"[1,2]\n,[3]"), (6, 2, 1));
+        assert_eq!(syntax_error(b"{\"\xc3\xa9\": tru}"), (10, 1, 10));
+        assert_eq!(syntax_error(b"[01]"), (2, 1, 3));
+        assert_eq!(syntax_error(b"\"a\\x\""), (3, 1, 4));
+    }
+
+    #[test]
+    fn strict_rejects_adjacent_values() {
+        assert_eq!(syntax_error(b"truefalse"), (4, 1, 5));
+        assert_eq!(syntax_error(b"\"a\"\"b\""), (3, 1, 4));
+        assert_eq!(syntax_error(b"{}1"), (2, 1, 3));
+        assert_eq!(validate(b"{}[]{\"a\":1}").records, 3);
+        assert_eq!(validate(b"1 2/* c */3\x1e4").records, 4);
+    }
+
+    #[test]
+    fn strict_rejects_incomplete_input() {
+        assert_eq!(syntax_error(b"{\"a\": [1"), (8, 1, 9));
+        assert_eq!(syntax_error(b"/* open"), (7, 1, 8));
+        assert!(validate(b"-").error.is_some());
+    }
+
+    #[test]
+    fn strict_detector_rejects_stray_comma() {
+        let mut d = JsonDetector::strict();
+        assert!(matches!(
+            d.feed(b"[1,2],[3,4]"),
+            Err(DetectorError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn strict_detector_reads_whole_stream() {
+        let mut d = JsonDetector::strict();
+        assert_eq!(d.feed(b"{\"a\":1}\n"), Ok(false));
+        assert_eq!(d.feed(b"{\"a\":2}\n"), Ok(false));
+        let (kind, summary) = d.finish_with_summary().unwrap();
+        assert_eq!(kind, JsonKind::JsonLines);
+        assert_eq!(summary.unwrap().records, 2);
+    }
+
+    #[test]
+    fn strict_detector_validates_chunked_input() {
+        let mut d = JsonDetector::strict();
+        for chunk in [&b"[1.2"[..], b"5e1", b", \"x\\", b"\"\"]"] {
+            d.feed(chunk).unwrap();
+        }
+        assert_eq!(d.finish(), Ok(JsonKind::JsonArray));
+    }
+
+    // -- Concatenated JSON and JSON text sequences ---------------------------
+
+    #[test]
+    fn pretty_printed_object_is_concatenated() {
+        assert_eq!(
+            detect(b"{\n  \"a\": 1\n}\n{\n  \"a\": 2\n}"),
+            Ok(JsonKind::ConcatenatedJson)
+        );
+    }
+
+    #[test]
+    fn objects_on_one_line_are_concatenated() {
+        assert_eq!(
+            detect(b"{\"a\":1} {\"a\":2}"),
+            Ok(JsonKind::ConcatenatedJson)
+        );
+    }
+
+    #[test]
+    fn newline_in_string_does_not_make_concatenated() {
+        assert_eq!(
+            detect(b"{\"a\":\"}\\n{\"}\r\n{\"a\":2}"),
+            Ok(JsonKind::JsonLines)
+        );
+    }
+
+    #[test]
+    fn record_separator_is_jsonseq() {
+        assert_eq!(
+            detect(b"\x1e{\"a\":1}\n\x1e{\"a\":2}\n"),
+            Ok(JsonKind::JsonSeq)
+        );
+    }
+
+    #[test]
+    fn strict_accepts_jsonseq() {
+        let summary = validate(b"\x1e{\"a\":1}\n\x1e[1,\n2]\n\x1e3\n");
+        assert_eq!(summary.error, None);
+        assert_eq!(summary.records, 3);
+    }
+
+    #[test]
+    fn pretty_printed_object_chunked() {
+        assert_eq!(
+            detect_chunked(&[b"{\"a\": {", b"\"b\": 1}", b"\n}"]),
+            Ok(JsonKind::ConcatenatedJson)
+        );
+    }
 }
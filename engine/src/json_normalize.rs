@@ -0,0 +1,212 @@
+use std::borrow::Cow;
+
+use datafusion::arrow::datatypes::DataType;
+use serde::de::IgnoredAny;
+use serde_json::{Map, Value};
+
+use crate::json_infer::RECORD_SEPARATOR;
+
+/// Replaces the record separators of a JSON text sequence with spaces, so that it can be
+/// parsed as a stream of whitespace-separated values.
+///
+/// Record separators are control characters, which cannot appear unescaped inside JSON
+/// strings, so this never changes the meaning of a value.
+pub fn strip_record_separators(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if !bytes.contains(&RECORD_SEPARATOR) {
+        return Cow::Borrowed(bytes);
+    }
+    let bytes = bytes
+        .iter()
+        .map(|&b| if b == RECORD_SEPARATOR { b' ' } else { b })
+        .collect();
+    Cow::Owned(bytes)
+}
+
+/// Rewrites concatenated or pretty-printed JSON values, or a JSON text sequence, as
+/// JSON Lines with one value per line.
+pub fn to_json_lines(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let bytes = strip_record_separators(bytes);
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut values = serde_json::Deserializer::from_slice(&bytes).into_iter::<IgnoredAny>();
+    let mut start = 0;
+    while let Some(value) = values.next() {
+        value.map_err(|err| format!("invalid JSON: {err}"))?;
+        let end = values.byte_offset();
+        // Line breaks must be escaped inside strings, so any raw ones are whitespace
+        // between tokens and can be replaced.
+        let value = bytes[start..end]
+            .iter()
+            .skip_while(|b| b.is_ascii_whitespace());
+        output.extend(value.map(|&b| if b == b'\n' || b == b'\r' { b' ' } else { b }));
+        output.push(b'\n');
+        start = end;
+    }
+    Ok(output)
+}
+
+/// Rewrites a stream of JSON values as JSON Lines, with each value wrapped in an object under
+/// `field` and coerced to `data_type` so that streams of mixed values can be read.
+pub fn wrap_values(bytes: &[u8], field: &str, data_type: &DataType) -> Result<Vec<u8>, String> {
+    let bytes = strip_record_separators(bytes);
+    let mut output = Vec::with_capacity(bytes.len());
+    for value in serde_json::Deserializer::from_slice(&bytes).into_iter::<Value>() {
+        let value = value.map_err(|err| format!("invalid JSON: {err}"))?;
+        let value = coerce_value(value, data_type);
+        let record: Map<_, _> = std::iter::once((field.to_string(), value)).collect();
+        serde_json::to_writer(&mut output, &record).map_err(|err| err.to_string())?;
+        output.push(b'\n');
+    }
+    Ok(output)
+}
+
+/// Converts a JSON value to the shape arrow's JSON reader expects for `data_type`, following
+/// the rules used by schema inference when it merges mixed values:
+///
+/// - anything read into a string column is kept as JSON text
+/// - a scalar read into a list column becomes a list of one element
+pub fn coerce_value(value: Value, data_type: &DataType) -> Value {
+    match (value, data_type) {
+        (Value::Null, _) => Value::Null,
+        (Value::String(value), DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View) => {
+            Value::String(value)
+        }
+        (value, DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View) => {
+            Value::String(value.to_string())
+        }
+        (Value::Array(values), DataType::List(field) | DataType::LargeList(field)) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| coerce_value(value, field.data_type()))
+                .collect(),
+        ),
+        (value, DataType::List(field) | DataType::LargeList(field)) => {
+            Value::Array(vec![coerce_value(value, field.data_type())])
+        }
+        (Value::Object(record), DataType::Struct(fields)) => Value::Object(
+            record
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = match fields.find(&name) {
+                        Some((_, field)) => coerce_value(value, field.data_type()),
+                        None => value,
+                    };
+                    (name, value)
+                })
+                .collect(),
+        ),
+        (value, _) => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::RecordBatch;
+    use datafusion::arrow::datatypes::Field;
+    use datafusion::arrow::json::reader::{infer_json_schema_from_iterator, ReaderBuilder};
+    use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+
+    use super::*;
+
+    fn normalize(input: &[u8]) -> String {
+        String::from_utf8(to_json_lines(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn pretty_printed_objects() {
+        let input = b"{\n  \"a\": 1,\r\n  \"b\": \"x\\ny\"\n}\n\n{\"a\": 2}";
+        assert_eq!(
+            normalize(input),
+            "{   \"a\": 1,    \"b\": \"x\\ny\" }\n{\"a\": 2}\n"
+        );
+    }
+
+    #[test]
+    fn objects_on_one_line() {
+        assert_eq!(
+            normalize(b"{\"a\":1}{\"a\":2} {\"a\":3}"),
+            "{\"a\":1}\n{\"a\":2}\n{\"a\":3}\n"
+        );
+    }
+
+    #[test]
+    fn json_text_sequence() {
+        let input = b"\x1e{\"a\":1}\n\x1e{\n\"a\":2}\n";
+        assert_eq!(normalize(input), "{\"a\":1}\n{ \"a\":2}\n");
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert!(to_json_lines(b"{\"a\":1}\n{\"a\":").is_err());
+    }
+
+    /// Infers the type of a stream of values, wraps them and reads them back with arrow's
+    /// JSON reader, returning the column type and its values formatted as text.
+    fn read_values(input: &str) -> (DataType, Vec<String>) {
+        let stripped = strip_record_separators(input.as_bytes());
+        let values = serde_json::Deserializer::from_slice(&stripped).into_iter::<Value>();
+        let records = values.map(|value| {
+            let record: Map<_, _> =
+                std::iter::once(("value".to_string(), value.unwrap())).collect();
+            Ok(Value::Object(record))
+        });
+        let schema = Arc::new(infer_json_schema_from_iterator(records).unwrap());
+        let data_type = schema.field(0).data_type().clone();
+
+        let output = wrap_values(input.as_bytes(), "value", &data_type).unwrap();
+        let batches: Vec<RecordBatch> = ReaderBuilder::new(schema)
+            .build(Cursor::new(output))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let options = FormatOptions::default().with_null("null");
+        let mut formatted = vec![];
+        for batch in batches {
+            let formatter = ArrayFormatter::try_new(batch.column(0), &options).unwrap();
+            formatted.extend((0..batch.num_rows()).map(|i| formatter.value(i).to_string()));
+        }
+        (data_type, formatted)
+    }
+
+    fn list_of(data_type: DataType) -> DataType {
+        DataType::List(Arc::new(Field::new("item", data_type, true)))
+    }
+
+    #[test]
+    fn wraps_numbers() {
+        let (data_type, values) = read_values("1\n2.5\n-3\nnull");
+        assert_eq!(data_type, DataType::Float64);
+        assert_eq!(values, ["1.0", "2.5", "-3.0", "null"]);
+    }
+
+    #[test]
+    fn wraps_strings() {
+        let (data_type, values) = read_values("\"a\" \"b\\nc\"\n\"\"");
+        assert_eq!(data_type, DataType::Utf8);
+        assert_eq!(values, ["a", "b\nc", ""]);
+    }
+
+    #[test]
+    fn wraps_nested_arrays() {
+        let (data_type, values) = read_values("[[1, 2], [3]]\n[]\n[[4]]");
+        assert_eq!(data_type, list_of(list_of(DataType::Int64)));
+        assert_eq!(values, ["[[1, 2], [3]]", "[]", "[[4]]"]);
+    }
+
+    #[test]
+    fn wraps_mixed_scalars_as_strings() {
+        let (data_type, values) = read_values("1 \"a\" true");
+        assert_eq!(data_type, DataType::Utf8);
+        assert_eq!(values, ["1", "a", "true"]);
+    }
+
+    #[test]
+    fn wraps_mixed_scalars_and_arrays_as_lists() {
+        let (data_type, values) = read_values("1\n[2, 3]\n\x1e4");
+        assert_eq!(data_type, list_of(DataType::Int64));
+        assert_eq!(values, ["[1]", "[2, 3]", "[4]"]);
+    }
+}
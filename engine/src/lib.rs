@@ -2,21 +2,49 @@ use std::sync::Arc;
 
 use datafusion::arrow::datatypes::{Schema, SchemaRef};
 use wasm_bindgen::prelude::*;
-use wasm_bindgen_futures::JsFuture;
 
-use crate::file_format::FileFormat;
-use crate::json_infer::{JsonDetector, JsonKind};
-use crate::utils::chunk_ranges;
+use crate::json_infer::{JsonKind, JsonSummary};
+use crate::parquet::ParquetMetadata;
+use crate::schema_report::SchemaReport;
+use crate::source::BlobSource;
 
+mod cell;
+mod compute;
+mod copy;
+mod explain;
+mod export;
 mod file_format;
+pub mod infer;
 mod json_infer;
+mod json_normalize;
+mod malformed;
+mod memory;
+mod metrics;
+mod nested;
+mod page_cache;
+mod parquet;
 mod plan;
+mod profile;
 mod record_set;
+mod schema_report;
+mod search;
+mod source;
 mod utils;
+mod worker;
+
+pub use export::ExportFormat;
+pub use file_format::FileFormat;
+pub use malformed::MalformedRowPolicy;
+pub use plan::Plan;
+pub use record_set::RecordSet;
+pub use source::{BytesSource, InputSource};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use source::LocalFileSource;
 
 #[wasm_bindgen(js_name = "Schema")]
 #[derive(Clone)]
-pub struct JsSchema(SchemaRef);
+pub struct JsSchema(SchemaRef, Option<SchemaReport>);
 
 impl JsSchema {
     pub fn inner(&self) -> &SchemaRef {
@@ -27,37 +55,22 @@ impl JsSchema {
 #[wasm_bindgen(js_class = "Schema")]
 impl JsSchema {
     pub fn empty() -> Self {
-        Self(Arc::new(Schema::empty()))
+        Self(Arc::new(Schema::empty()), None)
     }
 
     pub fn to_string(&self) -> String {
         self.inner().to_string()
     }
+
+    /// Explains how each column's type was inferred, if a report was requested.
+    pub fn report(&self) -> Option<SchemaReport> {
+        self.1.clone()
+    }
 }
 
 #[wasm_bindgen]
 pub async fn infer_file_format(file: &web_sys::File) -> Result<FileFormat, String> {
-    let filename = file.name();
-    let ext = std::path::Path::new(&filename)
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .ok_or("no file extension")?;
-
-    Ok(match ext {
-        "csv" => {
-            let encoding = infer_file_encoding(file).await?;
-            FileFormat::Csv { encoding, has_headers: true }
-        }
-        "json" | "jsonl" => {
-            let kind = infer_json_kind(file).await?;
-            FileFormat::Json {
-                flatten_top_level_arrays: kind == JsonKind::JsonArray,
-                single_field: (kind == JsonKind::JsonValues).then(|| "value".to_string()),
-            }
-        }
-        "parquet" => FileFormat::Parquet,
-        ext => Err(format!("unknown file extension: {ext}"))?,
-    })
+    infer::infer_format(&BlobSource::new(file.clone().into())).await
 }
 
 #[wasm_bindgen]
@@ -65,72 +78,35 @@ pub async fn infer_file_schema(
     file: &web_sys::Blob,
     format: FileFormat,
     max_records: Option<usize>,
+    report: Option<bool>,
 ) -> Result<JsSchema, String> {
-    use datafusion::arrow::csv::reader::Format as CsvFormat;
-    use datafusion::arrow::json::reader::{infer_json_schema_with_options, InferJsonSchemaOptions};
-    use datafusion::parquet::arrow::ParquetRecordBatchStreamBuilder;
-
-    let bytes = JsFuture::from(file.array_buffer()).await.unwrap();
-    let bytes = js_sys::Uint8Array::new(&bytes).to_vec();
-    let reader = std::io::Cursor::new(bytes);
-
-    let schema = match format.clone() {
-        FileFormat::Csv { has_headers, .. } => {
-            let (schema, _) = CsvFormat::default()
-                .with_header(has_headers)
-                .infer_schema(reader, max_records)
-                .map_err(|err| err.to_string())?;
-            Arc::new(schema)
-        }
-        FileFormat::Json { flatten_top_level_arrays, single_field } => {
-            let options = InferJsonSchemaOptions {
-                max_read_records: max_records,
-                flatten_top_level_arrays,
-                single_field,
-                ..Default::default()
-            };
-            let (schema, _) =
-                infer_json_schema_with_options(reader, options).map_err(|err| err.to_string())?;
-            Arc::new(schema)
-        }
-        FileFormat::Parquet { .. } => {
-            let reader = ParquetRecordBatchStreamBuilder::new(reader)
-                .await
-                .map_err(|err| err.to_string())?;
-            Arc::clone(reader.schema())
-        }
-    };
-    let schema = JsSchema(schema);
-
-    Ok(schema)
+    let source = BlobSource::new(file.clone());
+    let (schema, report) =
+        infer::infer_schema(&source, &format, max_records, report.unwrap_or(false)).await?;
+    Ok(JsSchema(schema, report))
+}
+
+/// Reads the metadata in the footer of a Parquet file, without reading any of its data.
+#[wasm_bindgen]
+pub async fn inspect_parquet(file: &web_sys::Blob) -> Result<ParquetMetadata, String> {
+    infer::inspect_parquet(&BlobSource::new(file.clone())).await
 }
 
 #[wasm_bindgen]
 pub async fn infer_file_encoding(file: &web_sys::File) -> Result<String, String> {
-    let mut detector = chardet::UniversalDetector::new();
-    for (range, _) in chunk_ranges(file.size() as _, 64 * 1024) {
-        let bytes = file
-            .slice_with_i32_and_i32(range.start as _, range.end as _)
-            .map_err(|_| "cannot read file".to_string())?;
-        let bytes = JsFuture::from(bytes.array_buffer()).await.unwrap();
-        let bytes = js_sys::Uint8Array::new(&bytes).to_vec();
-        detector.feed(&bytes);
-    }
-    Ok(detector.close().0)
+    infer::infer_encoding(&BlobSource::new(file.clone().into())).await
 }
 
 #[wasm_bindgen]
-pub async fn infer_json_kind(file: &web_sys::File) -> Result<JsonKind, String> {
-    let mut detector = JsonDetector::new();
-    for (range, _) in chunk_ranges(file.size() as _, 64 * 1024) {
-        let bytes = file
-            .slice_with_i32_and_i32(range.start as _, range.end as _)
-            .map_err(|_| "cannot read file".to_string())?;
-        let bytes = JsFuture::from(bytes.array_buffer()).await.unwrap();
-        let bytes = js_sys::Uint8Array::new(&bytes).to_vec();
-        if detector.feed(&bytes).map_err(|err| err.to_string())? {
-            break;
-        }
-    }
-    detector.finish().map_err(|err| err.to_string())
+pub async fn infer_json_kind(
+    file: &web_sys::File,
+    strict: Option<bool>,
+) -> Result<JsonKind, String> {
+    let source = BlobSource::new(file.clone().into());
+    infer::infer_json_kind(&source, strict.unwrap_or(false)).await
+}
+
+#[wasm_bindgen]
+pub async fn validate_json(file: &web_sys::File) -> Result<JsonSummary, String> {
+    infer::validate_json(&BlobSource::new(file.clone().into())).await
 }
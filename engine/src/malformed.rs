@@ -0,0 +1,728 @@
+use datafusion::arrow::array::{Array, StringArray};
+use datafusion::arrow::compute::{cast_with_options, CastOptions};
+use datafusion::arrow::datatypes::{DataType, Schema};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tsify::Tsify;
+
+use crate::file_format::FileFormat;
+
+/// The number of records whose fields are validated together.
+const CHUNK_SIZE: usize = 1024;
+
+/// What to do with rows that cannot be read using the file's schema.
+#[derive(Tsify, Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub enum MalformedRowPolicy {
+    /// Let the scan fail on the first malformed row.
+    #[default]
+    FailFast,
+    /// Leave malformed rows out of the scan.
+    SkipRow,
+    /// Replace fields that cannot be read with nulls. Rows with the wrong shape are skipped.
+    NullFields,
+}
+
+/// A row that was skipped or had fields nulled while reading a file.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct MalformedRow {
+    /// The byte offset of the start of the row in the file.
+    pub offset: u64,
+    /// The one-based line number of the start of the row in the file.
+    pub line: u64,
+    /// The text of the row, as it appears in the file.
+    pub raw: String,
+    pub reason: String,
+    /// Whether the row was left out of the scan, rather than having fields nulled.
+    pub skipped: bool,
+}
+
+/// A copy of a file with its malformed rows removed or repaired.
+pub struct Cleaned {
+    pub bytes: Vec<u8>,
+    /// The format of `bytes`, which may differ from that of the original file.
+    pub format: FileFormat,
+    pub malformed_rows: Vec<MalformedRow>,
+}
+
+/// Rewrites a file so that every row can be read using `schema`, according to `policy`.
+///
+/// Returns `None` if the format does not need cleaning.
+pub fn clean(
+    bytes: &[u8],
+    format: &FileFormat,
+    schema: &Schema,
+    policy: MalformedRowPolicy,
+) -> Result<Option<Cleaned>, String> {
+    if policy == MalformedRowPolicy::FailFast {
+        return Ok(None);
+    }
+    match format {
+        FileFormat::Csv { has_headers, .. } => {
+            let (bytes, malformed_rows) = clean_csv(bytes, schema, *has_headers, policy)?;
+            Ok(Some(Cleaned {
+                bytes,
+                format: format.clone(),
+                malformed_rows,
+            }))
+        }
+        FileFormat::Json {
+            flatten_top_level_arrays,
+            single_field,
+            concatenated,
+        } => {
+            // Files of single values are read as a stream, like concatenated files, since
+            // their values may span or share lines.
+            let concatenated = *concatenated || single_field.is_some();
+            let records = split_json(bytes, *flatten_top_level_arrays, concatenated)?;
+            let (bytes, malformed_rows) =
+                clean_json(bytes, records, schema, single_field.as_deref(), policy)?;
+            let format = FileFormat::Json {
+                flatten_top_level_arrays: false,
+                single_field: None,
+                concatenated: false,
+            };
+            Ok(Some(Cleaned { bytes, format, malformed_rows }))
+        }
+        FileFormat::Parquet { .. } => Ok(None),
+    }
+}
+
+fn clean_csv(
+    bytes: &[u8],
+    schema: &Schema,
+    has_headers: bool,
+    policy: MalformedRowPolicy,
+) -> Result<(Vec<u8>, Vec<MalformedRow>), String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(bytes);
+    let mut writer = csv::Writer::from_writer(vec![]);
+    let mut malformed_rows = vec![];
+
+    let mut chunk: Vec<(csv::StringRecord, RowLocation)> = vec![];
+    let mut record = csv::ByteRecord::new();
+    let mut skip_header = has_headers;
+    while reader
+        .read_byte_record(&mut record)
+        .map_err(|err| err.to_string())?
+    {
+        let start = record
+            .position()
+            .cloned()
+            .unwrap_or_else(csv::Position::new);
+        let location = RowLocation::new(bytes, &start, reader.position().byte());
+
+        if std::mem::take(&mut skip_header) {
+            writer
+                .write_byte_record(&record)
+                .map_err(|err| err.to_string())?;
+            continue;
+        }
+
+        let record = match csv::StringRecord::from_byte_record(record.clone()) {
+            Ok(record) if record.len() == schema.fields().len() => record,
+            Ok(record) => {
+                let reason = format!(
+                    "expected {} fields but found {}",
+                    schema.fields().len(),
+                    record.len()
+                );
+                malformed_rows.push(location.skipped(reason));
+                continue;
+            }
+            Err(_) => {
+                malformed_rows.push(location.skipped("invalid UTF-8".to_string()));
+                continue;
+            }
+        };
+
+        chunk.push((record, location));
+        if chunk.len() == CHUNK_SIZE {
+            flush_csv(&mut chunk, schema, policy, &mut writer, &mut malformed_rows)?;
+        }
+    }
+    flush_csv(&mut chunk, schema, policy, &mut writer, &mut malformed_rows)?;
+
+    // Rows with the wrong shape are reported before their chunk is flushed.
+    malformed_rows.sort_by_key(|row| row.offset);
+
+    let bytes = writer.into_inner().map_err(|err| err.to_string())?;
+    Ok((bytes, malformed_rows))
+}
+
+fn flush_csv(
+    chunk: &mut Vec<(csv::StringRecord, RowLocation)>,
+    schema: &Schema,
+    policy: MalformedRowPolicy,
+    writer: &mut csv::Writer<Vec<u8>>,
+    malformed_rows: &mut Vec<MalformedRow>,
+) -> Result<(), String> {
+    let invalid: Vec<_> = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(column, field)| {
+            let values: Vec<_> = chunk
+                .iter()
+                .map(|(record, _)| record.get(column).filter(|value| !value.is_empty()))
+                .collect();
+            invalid_values(&values, field.data_type())
+        })
+        .collect();
+
+    for (row, (record, location)) in chunk.drain(..).enumerate() {
+        let bad_columns: Vec<_> = (0..invalid.len()).filter(|&c| invalid[c][row]).collect();
+        if bad_columns.is_empty() {
+            writer
+                .write_record(&record)
+                .map_err(|err| err.to_string())?;
+            continue;
+        }
+
+        let reason = bad_columns
+            .iter()
+            .map(|&column| describe_invalid(schema, column, &format!("{:?}", &record[column])))
+            .collect::<Vec<_>>()
+            .join("; ");
+        match policy {
+            MalformedRowPolicy::NullFields => {
+                let record = record.iter().enumerate().map(|(column, value)| {
+                    if bad_columns.contains(&column) {
+                        ""
+                    } else {
+                        value
+                    }
+                });
+                writer.write_record(record).map_err(|err| err.to_string())?;
+                malformed_rows.push(location.nulled(reason));
+            }
+            _ => malformed_rows.push(location.skipped(reason)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits a JSON file into the byte ranges of its records.
+///
+/// Syntax errors in newline-delimited files only affect the line they occur on, but a syntax
+/// error inside a top-level array or a stream of concatenated values makes the rest of the
+/// file unreadable and is returned as an error.
+fn split_json(
+    bytes: &[u8],
+    flatten_top_level_arrays: bool,
+    concatenated: bool,
+) -> Result<Vec<JsonRecord>, String> {
+    let mut records = vec![];
+    let mut lines = LineCounter::default();
+
+    if concatenated {
+        let stripped = crate::json_normalize::strip_record_separators(bytes);
+        let mut values = serde_json::Deserializer::from_slice(&stripped).into_iter::<Value>();
+        let mut offset = 0;
+        while let Some(value) = values.next() {
+            let start = skip_whitespace(&stripped, offset);
+            let value = value.map_err(|err| format!("invalid JSON: {err}"))?;
+            offset = values.byte_offset();
+            let location = RowLocation::at(bytes, start, offset, lines.line_at(bytes, start));
+            records.push((location, Ok(value)));
+        }
+        return Ok(records);
+    }
+
+    if !flatten_top_level_arrays {
+        let mut offset = 0;
+        for line in bytes.split_inclusive(|&b| b == b'\n') {
+            let start = offset;
+            offset += line.len();
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let location = RowLocation::at(bytes, start, offset, lines.line_at(bytes, start));
+            let value = serde_json::from_slice(line).map_err(|err| err.to_string());
+            records.push((location, value));
+        }
+        return Ok(records);
+    }
+
+    let mut offset = skip_whitespace(bytes, 0);
+    if bytes.get(offset) != Some(&b'[') {
+        // Not an array; treat the file as a single record.
+        let location = RowLocation::at(bytes, offset, bytes.len(), lines.line_at(bytes, offset));
+        let value = serde_json::from_slice(bytes).map_err(|err| err.to_string());
+        return Ok(vec![(location, value)]);
+    }
+
+    offset = skip_whitespace(bytes, offset + 1);
+    if bytes.get(offset) == Some(&b']') {
+        return Ok(records);
+    }
+    loop {
+        let mut values = serde_json::Deserializer::from_slice(&bytes[offset..]).into_iter();
+        let value: Value = match values.next() {
+            Some(Ok(value)) => value,
+            Some(Err(err)) => {
+                let line = lines.line_at(bytes, offset);
+                return Err(format!(
+                    "invalid JSON in array element at line {line}: {err}"
+                ));
+            }
+            None => return Err("unterminated top-level array".to_string()),
+        };
+        let end = offset + values.byte_offset();
+        let location = RowLocation::at(bytes, offset, end, lines.line_at(bytes, offset));
+        records.push((location, Ok(value)));
+
+        offset = skip_whitespace(bytes, end);
+        match bytes.get(offset) {
+            Some(b',') => offset = skip_whitespace(bytes, offset + 1),
+            Some(b']') => break,
+            _ => {
+                let line = lines.line_at(bytes, offset);
+                return Err(format!(
+                    "expected `,` or `]` in top-level array at line {line}"
+                ));
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+fn clean_json(
+    bytes: &[u8],
+    records: Vec<JsonRecord>,
+    schema: &Schema,
+    single_field: Option<&str>,
+    policy: MalformedRowPolicy,
+) -> Result<(Vec<u8>, Vec<MalformedRow>), String> {
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut malformed_rows = vec![];
+    let mut chunk = vec![];
+
+    for (location, value) in records {
+        let record = match (value, single_field) {
+            (Ok(value), Some(field)) => {
+                let value = match schema.field_with_name(field) {
+                    Ok(field) => crate::json_normalize::coerce_value(value, field.data_type()),
+                    Err(_) => value,
+                };
+                std::iter::once((field.to_string(), value)).collect()
+            }
+            (Ok(Value::Object(record)), None) => record,
+            (Ok(_), None) => {
+                malformed_rows.push(location.skipped("expected a JSON object".to_string()));
+                continue;
+            }
+            (Err(err), _) => {
+                malformed_rows.push(location.skipped(err));
+                continue;
+            }
+        };
+
+        chunk.push((record, location));
+        if chunk.len() == CHUNK_SIZE {
+            flush_json(&mut chunk, schema, policy, &mut output, &mut malformed_rows)?;
+        }
+    }
+    flush_json(&mut chunk, schema, policy, &mut output, &mut malformed_rows)?;
+    malformed_rows.sort_by_key(|row| row.offset);
+
+    Ok((output, malformed_rows))
+}
+
+fn flush_json(
+    chunk: &mut Vec<(Map<String, Value>, RowLocation)>,
+    schema: &Schema,
+    policy: MalformedRowPolicy,
+    output: &mut Vec<u8>,
+    malformed_rows: &mut Vec<MalformedRow>,
+) -> Result<(), String> {
+    let invalid: Vec<_> = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let data_type = field.data_type();
+            let values: Vec<_> = chunk
+                .iter()
+                .map(|(record, _)| record.get(field.name()).unwrap_or(&Value::Null))
+                .collect();
+
+            let mut invalid: Vec<_> = values
+                .iter()
+                .map(|v| !json_kind_fits(v, data_type))
+                .collect();
+            if !is_nested(data_type) && !is_string(data_type) {
+                let epoch = is_epoch(data_type);
+                let text: Vec<_> = values
+                    .iter()
+                    .map(|value| match value {
+                        Value::String(value) => Some(value.clone()),
+                        // Checked below, since numbers are read as epoch values rather than cast.
+                        Value::Number(_) if epoch => None,
+                        Value::Number(value) => Some(value.to_string()),
+                        Value::Bool(value) => Some(value.to_string()),
+                        _ => None,
+                    })
+                    .collect();
+                let text: Vec<_> = text.iter().map(|value| value.as_deref()).collect();
+                let unparseable = invalid_values(&text, data_type);
+                invalid
+                    .iter_mut()
+                    .zip(unparseable)
+                    .for_each(|(a, b)| *a |= b);
+                if epoch {
+                    invalid
+                        .iter_mut()
+                        .zip(&values)
+                        .for_each(|(a, value)| *a |= value.is_number() && !value.is_i64());
+                }
+            }
+            invalid
+        })
+        .collect();
+
+    for (row, (mut record, location)) in chunk.drain(..).enumerate() {
+        let bad_columns: Vec<_> = (0..invalid.len()).filter(|&c| invalid[c][row]).collect();
+        if !bad_columns.is_empty() {
+            let reason = bad_columns
+                .iter()
+                .map(|&column| {
+                    let value = &record[schema.field(column).name()];
+                    describe_invalid(schema, column, &value.to_string())
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            if policy != MalformedRowPolicy::NullFields {
+                malformed_rows.push(location.skipped(reason));
+                continue;
+            }
+            for &column in &bad_columns {
+                record.insert(schema.field(column).name().clone(), Value::Null);
+            }
+            malformed_rows.push(location.nulled(reason));
+        }
+
+        serde_json::to_writer(&mut *output, &record).map_err(|err| err.to_string())?;
+        output.push(b'\n');
+    }
+
+    Ok(())
+}
+
+/// Returns whether the kind of a JSON value (object, array or scalar) suits `data_type`.
+fn json_kind_fits(value: &Value, data_type: &DataType) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Object(_) => matches!(data_type, DataType::Struct(_) | DataType::Map(..)),
+        Value::Array(_) => matches!(
+            data_type,
+            DataType::List(_) | DataType::LargeList(_) | DataType::FixedSizeList(..)
+        ),
+        Value::String(_) => !is_nested(data_type),
+        Value::Bool(_) | Value::Number(_) => !is_nested(data_type) && !is_string(data_type),
+    }
+}
+
+fn is_nested(data_type: &DataType) -> bool {
+    data_type.is_nested() || matches!(data_type, DataType::Map(..))
+}
+
+/// Returns whether the JSON reader reads integers of `data_type` as counts of its unit since the
+/// epoch, such as milliseconds for `Timestamp(Millisecond, _)`, which strings can't be cast to.
+fn is_epoch(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Timestamp(..)
+            | DataType::Date32
+            | DataType::Date64
+            | DataType::Time32(_)
+            | DataType::Time64(_)
+            | DataType::Duration(_)
+    )
+}
+
+fn is_string(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+    )
+}
+
+/// Returns, for each value, whether it is present but cannot be read as `data_type`.
+///
+/// Uses arrow's string casting rules, so that the check agrees with what the readers accept.
+/// Types that cannot be cast from strings are not checked.
+fn invalid_values(values: &[Option<&str>], data_type: &DataType) -> Vec<bool> {
+    if is_string(data_type) {
+        return vec![false; values.len()];
+    }
+
+    let array = StringArray::from(values.to_vec());
+    match cast_with_options(&array, data_type, &CastOptions::default()) {
+        Ok(cast) => (0..array.len())
+            .map(|i| array.is_valid(i) && cast.is_null(i))
+            .collect(),
+        Err(_) => vec![false; values.len()],
+    }
+}
+
+/// Describes a field that cannot be read, where `value` is already formatted for display.
+fn describe_invalid(schema: &Schema, column: usize, value: &str) -> String {
+    let field = schema.field(column);
+    format!(
+        "invalid {} value {value} in column `{}`",
+        field.data_type(),
+        field.name()
+    )
+}
+
+fn skip_whitespace(bytes: &[u8], offset: usize) -> usize {
+    offset
+        + bytes[offset.min(bytes.len())..]
+            .iter()
+            .take_while(|b| b.is_ascii_whitespace())
+            .count()
+}
+
+/// Finds line numbers for a sequence of increasing byte offsets.
+#[derive(Default)]
+struct LineCounter {
+    offset: usize,
+    newlines: u64,
+}
+
+impl LineCounter {
+    fn line_at(&mut self, bytes: &[u8], offset: usize) -> u64 {
+        let newlines = bytes[self.offset..offset]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count();
+        self.newlines += newlines as u64;
+        self.offset = offset;
+        self.newlines + 1
+    }
+}
+
+/// A JSON value, or the reason it couldn't be parsed, and where it was found.
+type JsonRecord = (RowLocation, Result<Value, String>);
+
+/// Where a row was found in the original file.
+struct RowLocation {
+    offset: u64,
+    line: u64,
+    raw: String,
+}
+
+impl RowLocation {
+    fn new(bytes: &[u8], start: &csv::Position, end: u64) -> Self {
+        Self::at(bytes, start.byte() as usize, end as usize, start.line())
+    }
+
+    fn at(bytes: &[u8], start: usize, end: usize, line: u64) -> Self {
+        let raw = String::from_utf8_lossy(&bytes[start..end]);
+        let raw = raw.trim_end_matches(['\r', '\n']).to_string();
+        Self { offset: start as u64, line, raw }
+    }
+
+    fn skipped(self, reason: String) -> MalformedRow {
+        self.into_row(reason, true)
+    }
+
+    fn nulled(self, reason: String) -> MalformedRow {
+        self.into_row(reason, false)
+    }
+
+    fn into_row(self, reason: String, skipped: bool) -> MalformedRow {
+        let Self { offset, line, raw } = self;
+        MalformedRow { offset, line, raw, reason, skipped }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::datatypes::{Field, TimeUnit};
+
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+        ])
+    }
+
+    fn csv_format() -> FileFormat {
+        FileFormat::Csv {
+            encoding: "utf-8".to_string(),
+            has_headers: true,
+        }
+    }
+
+    fn json_format(flatten_top_level_arrays: bool) -> FileFormat {
+        FileFormat::Json {
+            flatten_top_level_arrays,
+            single_field: None,
+            concatenated: false,
+        }
+    }
+
+    fn clean_str(
+        input: &str,
+        format: FileFormat,
+        policy: MalformedRowPolicy,
+    ) -> (String, Vec<MalformedRow>) {
+        let cleaned = clean(input.as_bytes(), &format, &schema(), policy)
+            .unwrap()
+            .unwrap();
+        (
+            String::from_utf8(cleaned.bytes).unwrap(),
+            cleaned.malformed_rows,
+        )
+    }
+
+    #[test]
+    fn fail_fast_does_not_clean() {
+        let cleaned = clean(
+            b"id,name\n",
+            &csv_format(),
+            &schema(),
+            MalformedRowPolicy::FailFast,
+        );
+        assert!(cleaned.unwrap().is_none());
+    }
+
+    #[test]
+    fn csv_skips_bad_rows() {
+        let input = "id,name\n1,a\nx,b\n3\n4,d\n";
+        let (output, rows) = clean_str(input, csv_format(), MalformedRowPolicy::SkipRow);
+        assert_eq!(output, "id,name\n1,a\n4,d\n");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            (rows[0].offset, rows[0].line, rows[0].raw.as_str()),
+            (12, 3, "x,b")
+        );
+        assert_eq!(rows[0].reason, "invalid Int64 value \"x\" in column `id`");
+        assert!(rows[0].skipped);
+        assert_eq!((rows[1].line, rows[1].raw.as_str()), (4, "3"));
+        assert_eq!(rows[1].reason, "expected 2 fields but found 1");
+    }
+
+    #[test]
+    fn csv_nulls_bad_fields() {
+        let input = "id,name\n1,a\nx,b\n";
+        let (output, rows) = clean_str(input, csv_format(), MalformedRowPolicy::NullFields);
+        assert_eq!(output, "id,name\n1,a\n,b\n");
+        assert_eq!(rows.len(), 1);
+        assert!(!rows[0].skipped);
+    }
+
+    #[test]
+    fn json_lines_skip_bad_lines() {
+        let input =
+            "{\"id\":1,\"name\":\"a\"}\n{\"id\":\n\n{\"id\":\"x\"}\n{\"id\":2,\"name\":3}\n";
+        let (output, rows) = clean_str(input, json_format(false), MalformedRowPolicy::SkipRow);
+        assert_eq!(output, "{\"id\":1,\"name\":\"a\"}\n");
+        let lines: Vec<_> = rows.iter().map(|row| row.line).collect();
+        assert_eq!(lines, [2, 4, 5]);
+        assert_eq!(rows[2].reason, "invalid Utf8 value 3 in column `name`");
+    }
+
+    #[test]
+    fn json_reads_numbers_as_epoch_values() {
+        let schema = Schema::new(vec![Field::new(
+            "at",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            true,
+        )]);
+        let input = "{\"at\":1700000000000}\n{\"at\":\"2023-11-14T22:13:20Z\"}\n\
+                     {\"at\":1.5}\n{\"at\":\"soon\"}\n";
+        let cleaned = clean(
+            input.as_bytes(),
+            &json_format(false),
+            &schema,
+            MalformedRowPolicy::NullFields,
+        )
+        .unwrap()
+        .unwrap();
+        let output = String::from_utf8(cleaned.bytes).unwrap();
+        assert_eq!(
+            output,
+            "{\"at\":1700000000000}\n{\"at\":\"2023-11-14T22:13:20Z\"}\n\
+             {\"at\":null}\n{\"at\":null}\n"
+        );
+        let lines: Vec<_> = cleaned.malformed_rows.iter().map(|row| row.line).collect();
+        assert_eq!(lines, [3, 4]);
+    }
+
+    #[test]
+    fn json_array_nulls_bad_fields() {
+        let input = "[\n  {\"id\": 1},\n  {\"id\": \"x\", \"name\": \"b\"}\n]";
+        let (output, rows) = clean_str(input, json_format(true), MalformedRowPolicy::NullFields);
+        assert_eq!(output, "{\"id\":1}\n{\"id\":null,\"name\":\"b\"}\n");
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].offset, rows[0].line), (17, 3));
+        assert_eq!(rows[0].raw, "{\"id\": \"x\", \"name\": \"b\"}");
+    }
+
+    #[test]
+    fn json_array_syntax_error_is_fatal() {
+        let input = "[{\"id\": 1}, {\"id\": }]";
+        let result = clean(
+            input.as_bytes(),
+            &json_format(true),
+            &schema(),
+            MalformedRowPolicy::SkipRow,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn concatenated_json_skips_bad_records() {
+        let format = FileFormat::Json {
+            flatten_top_level_arrays: false,
+            single_field: None,
+            concatenated: true,
+        };
+        let input = "\x1e{\n  \"id\": 1\n}\n\x1e{\n  \"id\": \"x\"\n}\n";
+        let (output, rows) = clean_str(input, format, MalformedRowPolicy::SkipRow);
+        assert_eq!(output, "{\"id\":1}\n");
+        assert_eq!((rows[0].offset, rows[0].line), (16, 4));
+        assert_eq!(rows[0].raw, "{\n  \"id\": \"x\"\n}");
+    }
+
+    #[test]
+    fn single_values_span_and_share_lines() {
+        let field = Field::new_list_field(DataType::Int64, true);
+        let schema = Schema::new(vec![Field::new(
+            "values",
+            DataType::List(std::sync::Arc::new(field)),
+            true,
+        )]);
+        let format = FileFormat::Json {
+            flatten_top_level_arrays: false,
+            single_field: Some("values".to_string()),
+            concatenated: false,
+        };
+        let input = "[1,\n2] [3]\n[4]\n";
+        let cleaned = clean(
+            input.as_bytes(),
+            &format,
+            &schema,
+            MalformedRowPolicy::SkipRow,
+        )
+        .unwrap()
+        .unwrap();
+        let output = String::from_utf8(cleaned.bytes).unwrap();
+        assert_eq!(
+            output,
+            "{\"values\":[1,2]}\n{\"values\":[3]}\n{\"values\":[4]}\n"
+        );
+        assert!(cleaned.malformed_rows.is_empty());
+    }
+}
@@ -0,0 +1,432 @@
+use std::io::Cursor;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::ipc::reader::FileReader;
+use datafusion::arrow::ipc::writer::{FileWriter, IpcWriteOptions};
+use datafusion::arrow::ipc::CompressionType;
+use datafusion::error::DataFusionError;
+use datafusion::execution::disk_manager::{DiskManagerBuilder, DiskManagerMode};
+use datafusion::execution::memory_pool::{
+    GreedyMemoryPool, MemoryConsumer, MemoryLimit, MemoryPool, MemoryReservation,
+    TrackConsumersPool, UnboundedMemoryPool,
+};
+use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
+use datafusion::object_store::memory::InMemory;
+use datafusion::object_store::path::Path;
+use datafusion::object_store::ObjectStore;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+/// The number of largest memory consumers named in "resources exhausted" errors.
+const REPORTED_CONSUMERS: usize = 5;
+
+/// Distinguishes the spill files of concurrent executions that share a store.
+static NEXT_EXECUTION: AtomicUsize = AtomicUsize::new(0);
+
+/// Limits how much memory a plan may use while it executes.
+#[derive(Tsify, Clone, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryOptions {
+    /// The most memory, in bytes, that the operators and collected results of an execution may
+    /// use at once. Unlimited if omitted.
+    #[tsify(optional)]
+    pub limit: Option<usize>,
+    /// Whether to move collected batches to the spill store when the limit is reached, rather
+    /// than failing.
+    ///
+    /// Only the collection of a plan's results spills. Operators such as sorts, joins and
+    /// group-bys can't, and still fail when they reach the limit, so spilling doesn't let them
+    /// process more data. It only leaves them the memory that the results collected so far would
+    /// have used, and the results must fit within the limit again once they are read back.
+    ///
+    /// Without a store given to [`crate::Plan::with_spill_store`], which the browser can't give
+    /// yet, the spilled batches are kept in memory, compressed, and stay reserved. They then only
+    /// free the memory that compressing them saves.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub spill: bool,
+}
+
+/// Where collected batches are moved when an execution reaches its memory limit.
+#[derive(Clone, Debug)]
+pub enum SpillStore {
+    /// Keeps the batches in memory, compressed. Only the memory that compressing them saves is
+    /// freed, since their encoded bytes stay reserved in the pool.
+    InMemory,
+    /// Moves the batches out of the memory pool to a store such as one backed by the browser's
+    /// origin private file system.
+    External(Arc<dyn ObjectStore>),
+}
+
+/// How much memory an execution used.
+#[derive(Tsify, Clone, Copy, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUsage {
+    /// The most memory reserved at once.
+    pub peak_bytes: usize,
+    #[tsify(optional)]
+    pub limit: Option<usize>,
+    /// The number of times collected batches were moved to the spill store.
+    pub spill_count: usize,
+    /// The size of the spilled batches once encoded.
+    pub spilled_bytes: usize,
+}
+
+/// A memory pool that remembers the most memory that was ever reserved from it.
+#[derive(Debug)]
+pub struct PeakMemoryPool {
+    inner: Arc<dyn MemoryPool>,
+    peak: AtomicUsize,
+}
+
+impl PeakMemoryPool {
+    pub fn new(limit: Option<usize>) -> Self {
+        let inner: Arc<dyn MemoryPool> = match limit {
+            Some(limit) => Arc::new(TrackConsumersPool::new(
+                GreedyMemoryPool::new(limit),
+                NonZeroUsize::new(REPORTED_CONSUMERS).unwrap(),
+            )),
+            None => Arc::new(UnboundedMemoryPool::default()),
+        };
+        Self { inner, peak: AtomicUsize::new(0) }
+    }
+
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    fn update_peak(&self) {
+        self.peak
+            .fetch_max(self.inner.reserved(), Ordering::Relaxed);
+    }
+}
+
+impl MemoryPool for PeakMemoryPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        self.inner.register(consumer)
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        self.inner.unregister(consumer)
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.inner.grow(reservation, additional);
+        self.update_peak();
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.inner.shrink(reservation, shrink)
+    }
+
+    fn try_grow(
+        &self,
+        reservation: &MemoryReservation,
+        additional: usize,
+    ) -> datafusion::error::Result<()> {
+        self.inner.try_grow(reservation, additional)?;
+        self.update_peak();
+        Ok(())
+    }
+
+    fn reserved(&self) -> usize {
+        self.inner.reserved()
+    }
+
+    fn memory_limit(&self) -> MemoryLimit {
+        self.inner.memory_limit()
+    }
+}
+
+/// Creates a runtime that takes memory from `pool`.
+///
+/// Spilling to temporary files is disabled, since there is no file system in the browser, so
+/// operators such as sorts and group-bys that run out of memory fail with a "resources
+/// exhausted" error instead. Only collected batches can spill, through [`Collector`].
+pub fn runtime_env(pool: Arc<PeakMemoryPool>) -> Result<Arc<RuntimeEnv>, String> {
+    RuntimeEnvBuilder::new()
+        .with_memory_pool(pool)
+        .with_disk_manager_builder(
+            DiskManagerBuilder::default().with_mode(DiskManagerMode::Disabled),
+        )
+        .build_arc()
+        .map_err(|err| err.to_string())
+}
+
+/// Describes an error that occurred while executing a plan, explaining how to recover when it
+/// ran out of memory.
+pub fn execution_error(err: DataFusionError, limit: Option<usize>) -> String {
+    match (err.find_root(), limit) {
+        (DataFusionError::ResourcesExhausted(message), Some(limit)) => format!(
+            "the query needs more than its memory limit of {limit} bytes; raise the limit, \
+             enable spilling or reduce the number of rows or columns ({message})"
+        ),
+        _ => format!("{err:?}"),
+    }
+}
+
+/// Collects the batches of an execution, accounting for them in the memory pool.
+///
+/// When the pool runs out of memory and a spill store is given, the batches collected so far are
+/// encoded as a compressed Arrow IPC file and moved to the store. They are read back, in order,
+/// once the execution has finished, and reserved again.
+pub struct Collector {
+    schema: SchemaRef,
+    reservation: MemoryReservation,
+    /// Reserves the encoded batches that are spilled to a store in memory.
+    spill_reservation: MemoryReservation,
+    spill_store: Option<Arc<dyn ObjectStore>>,
+    reserve_spilled: bool,
+    prefix: Path,
+    batches: Vec<RecordBatch>,
+    spill_files: Vec<Path>,
+    spilled_bytes: usize,
+}
+
+impl Collector {
+    pub fn new(
+        schema: SchemaRef,
+        pool: &Arc<dyn MemoryPool>,
+        spill_store: Option<SpillStore>,
+    ) -> Self {
+        let reservation = MemoryConsumer::new("collect")
+            .with_can_spill(spill_store.is_some())
+            .register(pool);
+        let (spill_store, reserve_spilled) = match spill_store {
+            Some(SpillStore::InMemory) => (Some(Arc::new(InMemory::new()) as _), true),
+            Some(SpillStore::External(store)) => (Some(store), false),
+            None => (None, false),
+        };
+        let execution = NEXT_EXECUTION.fetch_add(1, Ordering::Relaxed);
+        Self {
+            schema,
+            spill_reservation: reservation.new_empty(),
+            reservation,
+            spill_store,
+            reserve_spilled,
+            prefix: Path::from(format!("spill/{execution}")),
+            batches: vec![],
+            spill_files: vec![],
+            spilled_bytes: 0,
+        }
+    }
+
+    pub async fn push(&mut self, batch: RecordBatch) -> Result<(), DataFusionError> {
+        let size = batch.get_array_memory_size();
+        if let Err(err) = self.reservation.try_grow(size) {
+            match &self.spill_store {
+                Some(store) if !self.batches.is_empty() => {
+                    let store = store.clone();
+                    self.spill(store.as_ref()).await?;
+                    self.reservation.try_grow(size)?;
+                }
+                _ => return Err(err),
+            }
+        }
+        self.batches.push(batch);
+        Ok(())
+    }
+
+    /// Returns the collected batches, reading back any that were spilled. Fails if the batches
+    /// that are read back don't fit in the pool.
+    pub async fn finish(mut self) -> Result<Vec<RecordBatch>, DataFusionError> {
+        let Some(store) = self.spill_store.clone() else {
+            return Ok(std::mem::take(&mut self.batches));
+        };
+        let mut batches = vec![];
+        for path in &self.spill_files {
+            let bytes = store.get(path).await?.bytes().await?;
+            let encoded_size = bytes.len();
+            for batch in FileReader::try_new(Cursor::new(bytes), None)? {
+                let batch = batch?;
+                self.reservation.try_grow(batch.get_array_memory_size())?;
+                batches.push(batch);
+            }
+            store.delete(path).await?;
+            if self.reserve_spilled {
+                self.spill_reservation.shrink(encoded_size);
+            }
+        }
+        batches.append(&mut self.batches);
+        Ok(batches)
+    }
+
+    pub fn spill_count(&self) -> usize {
+        self.spill_files.len()
+    }
+
+    pub fn spilled_bytes(&self) -> usize {
+        self.spilled_bytes
+    }
+
+    async fn spill(&mut self, store: &dyn ObjectStore) -> Result<(), DataFusionError> {
+        let options =
+            IpcWriteOptions::default().try_with_compression(Some(CompressionType::LZ4_FRAME))?;
+        let mut writer = FileWriter::try_new_with_options(vec![], &self.schema, options)?;
+        for batch in self.batches.drain(..) {
+            writer.write(&batch)?;
+        }
+        let bytes = writer.into_inner()?;
+
+        self.reservation.free();
+        if self.reserve_spilled {
+            self.spill_reservation.try_grow(bytes.len())?;
+        }
+
+        let path = self.prefix.child(self.spill_files.len().to_string());
+        self.spilled_bytes += bytes.len();
+        store.put(&path, bytes.into()).await?;
+        self.spill_files.push(path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::{Array, Int64Array};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::physical_plan::execute_stream;
+    use datafusion::prelude::*;
+    use futures::executor::block_on;
+    use futures::TryStreamExt;
+
+    use super::*;
+
+    fn batch(start: i64) -> RecordBatch {
+        let schema = Schema::new(vec![Field::new("n", DataType::Int64, false)]);
+        let values = Int64Array::from_iter_values(start..start + 1000);
+        RecordBatch::try_new(Arc::new(schema), vec![Arc::new(values)]).unwrap()
+    }
+
+    fn values(batches: &[RecordBatch]) -> Vec<i64> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let column = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap();
+                column.values().to_vec()
+            })
+            .collect()
+    }
+
+    /// Collects five batches while another consumer holds `held` bytes, which it frees before
+    /// the spilled batches are read back.
+    fn collect(
+        limit: usize,
+        held: usize,
+        spill_store: Option<SpillStore>,
+    ) -> (Result<Vec<RecordBatch>, DataFusionError>, usize) {
+        let pool: Arc<PeakMemoryPool> = Arc::new(PeakMemoryPool::new(Some(limit)));
+        let mut operator = MemoryConsumer::new("operator").register(&(pool.clone() as _));
+        operator.grow(held);
+        let mut collector = Collector::new(batch(0).schema(), &(pool.clone() as _), spill_store);
+        let result = block_on(async {
+            for start in (0..5000).step_by(1000) {
+                collector.push(batch(start)).await?;
+            }
+            operator.free();
+            collector.finish().await
+        });
+        (result, pool.peak())
+    }
+
+    #[test]
+    fn spills_collected_batches() {
+        let store = Arc::new(InMemory::new());
+        let size = batch(0).get_array_memory_size();
+        let limit = 6 * size;
+        let spill_store = SpillStore::External(store.clone());
+        let (result, peak) = collect(limit, 4 * size, Some(spill_store));
+
+        assert_eq!(values(&result.unwrap()), (0..5000).collect::<Vec<_>>());
+        // The batches that are read back are reserved again.
+        assert!(peak >= 5 * size && peak <= limit, "{}", peak);
+
+        // Spill files are removed once they have been read back.
+        let files: Vec<_> = block_on(store.list(None).try_collect()).unwrap();
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn fails_when_spilled_batches_do_not_fit() {
+        let limit = 2 * batch(0).get_array_memory_size();
+        let spill_store = SpillStore::External(Arc::new(InMemory::new()));
+        let (result, _) = collect(limit, 0, Some(spill_store));
+        assert!(matches!(
+            result.unwrap_err().find_root(),
+            DataFusionError::ResourcesExhausted(_)
+        ));
+    }
+
+    #[test]
+    fn reserves_batches_spilled_in_memory() {
+        let pool = Arc::new(PeakMemoryPool::new(Some(
+            6 * batch(0).get_array_memory_size(),
+        )));
+        let mut collector = Collector::new(
+            batch(0).schema(),
+            &(pool.clone() as _),
+            Some(SpillStore::InMemory),
+        );
+        for start in (0..7000).step_by(1000) {
+            block_on(collector.push(batch(start))).unwrap();
+        }
+        assert_eq!(collector.spill_count(), 1);
+        let collected: usize = collector
+            .batches
+            .iter()
+            .map(|batch| batch.get_array_memory_size())
+            .sum();
+        assert_eq!(pool.reserved(), collected + collector.spilled_bytes());
+
+        // Spilling only made room to collect the batches, which don't fit once read back.
+        let err = block_on(collector.finish()).unwrap_err();
+        assert!(matches!(
+            err.find_root(),
+            DataFusionError::ResourcesExhausted(_)
+        ));
+    }
+
+    #[test]
+    fn fails_gracefully_without_a_spill_store() {
+        let limit = 2 * batch(0).get_array_memory_size();
+        let (result, _) = collect(limit, 0, None);
+        let message = execution_error(result.unwrap_err(), Some(limit));
+        assert!(message.starts_with("the query needs more than its memory limit"));
+        assert!(message.contains("collect"), "{}", message);
+    }
+
+    #[test]
+    fn limits_operators() {
+        let pool = Arc::new(PeakMemoryPool::new(Some(64 * 1024)));
+        let config = SessionConfig::new().with_target_partitions(1);
+        let ctx = SessionContext::new_with_config_rt(config, runtime_env(pool).unwrap());
+        let batches: Vec<_> = (0..100_000).step_by(1000).map(batch).collect();
+        let df = ctx
+            .read_batches(batches)
+            .unwrap()
+            .sort(vec![col("n").sort(false, false)])
+            .unwrap();
+
+        let err = block_on(async {
+            let plan = df.create_physical_plan().await?;
+            execute_stream(plan, ctx.task_ctx())?
+                .try_collect::<Vec<_>>()
+                .await
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err.find_root(),
+            DataFusionError::ResourcesExhausted(_)
+        ));
+    }
+}
@@ -0,0 +1,200 @@
+use datafusion::datasource::physical_plan::FileScanConfig;
+use datafusion::datasource::source::DataSourceExec;
+use datafusion::physical_plan::metrics::MetricValue;
+use datafusion::physical_plan::ExecutionPlan;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+/// How long a plan took to collect, and how much it read and held in memory.
+#[derive(Tsify, Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionMetrics {
+    /// The time, in milliseconds, spent optimizing the plan and creating its physical plan.
+    pub planning_millis: f64,
+    /// The time, in milliseconds, from starting the execution to collecting its last batch.
+    pub execution_millis: f64,
+    /// The groups of files the plan scanned, in the order of the partitions that scanned them.
+    pub file_groups: Vec<FileGroupMetrics>,
+    /// The rows scanned from all files.
+    pub rows_scanned: usize,
+    /// The bytes read from all files, or `None` if none of them reported it.
+    #[tsify(optional)]
+    pub bytes_scanned: Option<usize>,
+    /// The most memory, in bytes, that the operators and collected results used at once.
+    pub peak_memory_bytes: usize,
+    pub num_batches: usize,
+    pub output_rows: usize,
+}
+
+/// The files that one partition of a scan read, one after another. Scans only count the rows
+/// of each partition, so they can't be split between the group's files.
+#[derive(Tsify, Clone, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct FileGroupMetrics {
+    /// The files' paths within the object store they were read from.
+    pub paths: Vec<String>,
+    /// The rows output by the scan, after any filters that were pushed into it.
+    pub rows_scanned: usize,
+    /// The bytes read from the files, including their metadata. Only Parquet scans report it.
+    #[tsify(optional)]
+    pub bytes_scanned: Option<usize>,
+}
+
+impl ExecutionMetrics {
+    /// Sums the rows and bytes that each file scan in an executed plan read.
+    pub fn with_scans(self, plan: &dyn ExecutionPlan) -> Self {
+        let mut file_groups = vec![];
+        add_scans(plan, &mut file_groups);
+        let rows_scanned = file_groups.iter().map(|group| group.rows_scanned).sum();
+        let bytes_scanned = file_groups
+            .iter()
+            .filter_map(|group| group.bytes_scanned)
+            .reduce(|a, b| a + b);
+        Self {
+            file_groups,
+            rows_scanned,
+            bytes_scanned,
+            ..self
+        }
+    }
+}
+
+fn add_scans(plan: &dyn ExecutionPlan, file_groups: &mut Vec<FileGroupMetrics>) {
+    let config = plan
+        .as_any()
+        .downcast_ref::<DataSourceExec>()
+        .and_then(|exec| exec.data_source().as_any().downcast_ref::<FileScanConfig>());
+    if let (Some(config), Some(metrics)) = (config, plan.metrics()) {
+        // Each partition scans one file group.
+        let mut groups: Vec<FileGroupMetrics> = config
+            .file_groups
+            .iter()
+            .map(|group| FileGroupMetrics {
+                paths: group.iter().map(|file| file.path().to_string()).collect(),
+                ..Default::default()
+            })
+            .collect();
+
+        for metric in metrics.iter() {
+            match metric.value() {
+                MetricValue::OutputRows(count) => {
+                    if let Some(group) = metric
+                        .partition()
+                        .and_then(|partition| groups.get_mut(partition))
+                    {
+                        group.rows_scanned += count.value();
+                    }
+                }
+                MetricValue::Count { name, count } if name == "bytes_scanned" => {
+                    let Some(path) = metric
+                        .labels()
+                        .iter()
+                        .find(|label| label.name() == "filename")
+                        .map(|label| label.value())
+                    else {
+                        continue;
+                    };
+                    if let Some(group) = groups
+                        .iter_mut()
+                        .find(|group| group.paths.iter().any(|file| file == path))
+                    {
+                        *group.bytes_scanned.get_or_insert(0) += count.value();
+                    }
+                }
+                _ => {}
+            }
+        }
+        file_groups.append(&mut groups);
+    }
+    for child in plan.children() {
+        add_scans(child.as_ref(), file_groups);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Int64Array, RecordBatch};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::object_store::memory::InMemory;
+    use datafusion::object_store::path::Path;
+    use datafusion::object_store::ObjectStore;
+    use datafusion::parquet::arrow::ArrowWriter;
+    use datafusion::physical_plan::execute_stream_partitioned;
+    use datafusion::prelude::*;
+    use futures::executor::block_on;
+    use futures::TryStreamExt;
+    use url::Url;
+
+    use super::*;
+
+    /// Executes a filtered scan of the files in `store` over `partitions` partitions.
+    fn scan(store: &Arc<InMemory>, partitions: usize) -> Arc<dyn ExecutionPlan> {
+        let config = SessionConfig::new().with_target_partitions(partitions);
+        let ctx = SessionContext::new_with_config(config);
+        ctx.register_object_store(&Url::parse("memory:///").unwrap(), store.clone());
+        block_on(async {
+            let df = ctx
+                .read_parquet("memory:///data/", ParquetReadOptions::default())
+                .await?
+                .filter(col("n").gt_eq(lit(50i64)))?;
+            let plan = df.create_physical_plan().await?;
+            for stream in execute_stream_partitioned(plan.clone(), ctx.task_ctx())? {
+                stream.try_collect::<Vec<_>>().await?;
+            }
+            Ok::<_, datafusion::error::DataFusionError>(plan)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn sums_scans_per_file_group() {
+        let store = Arc::new(InMemory::new());
+        let schema = Arc::new(Schema::new(vec![Field::new("n", DataType::Int64, false)]));
+        for (name, values) in [("a", 0..100), ("b", 100..150)] {
+            let values = Int64Array::from_iter_values(values);
+            let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(values)]).unwrap();
+            let mut writer = ArrowWriter::try_new(vec![], schema.clone(), None).unwrap();
+            writer.write(&batch).unwrap();
+            let bytes = writer.into_inner().unwrap();
+            let path = Path::from(format!("data/{name}.parquet"));
+            block_on(store.put(&path, bytes.into())).unwrap();
+        }
+
+        let metrics = ExecutionMetrics::default().with_scans(scan(&store, 2).as_ref());
+        let paths: Vec<_> = metrics
+            .file_groups
+            .iter()
+            .map(|group| group.paths.clone())
+            .collect();
+        assert_eq!(paths, [["data/a.parquet"], ["data/b.parquet"]]);
+        // Filters aren't pushed into Parquet scans by default, so they output every row.
+        let rows: Vec<_> = metrics
+            .file_groups
+            .iter()
+            .map(|group| group.rows_scanned)
+            .collect();
+        assert_eq!(rows, [100, 50]);
+        assert_eq!(metrics.rows_scanned, 150);
+        let bytes: Vec<_> = metrics
+            .file_groups
+            .iter()
+            .map(|group| group.bytes_scanned)
+            .collect();
+        assert!(bytes.iter().all(|bytes| bytes.unwrap() > 0));
+        assert_eq!(metrics.bytes_scanned, Some(bytes.iter().flatten().sum()));
+
+        // Scans both files in one partition, whose rows and bytes are counted together.
+        let combined = ExecutionMetrics::default().with_scans(scan(&store, 1).as_ref());
+        assert_eq!(combined.file_groups.len(), 1);
+        assert_eq!(
+            combined.file_groups[0].paths,
+            ["data/a.parquet", "data/b.parquet"]
+        );
+        assert_eq!(combined.file_groups[0].rows_scanned, 150);
+        assert_eq!(combined.bytes_scanned, metrics.bytes_scanned);
+    }
+}
@@ -0,0 +1,235 @@
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{Column, DFSchema};
+use datafusion::functions::core::expr_fn::get_field;
+use datafusion::functions_nested::expr_fn::array_element;
+use datafusion::logical_expr::{lit, Expr};
+
+/// A single step in a path into a nested value, such as `$.customer.orders[0].total`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// A field of a struct, or a top-level column.
+    Field(String),
+    /// A zero-based index into a list. Negative indices count back from the end of the list.
+    Index(i64),
+}
+
+/// Parses a JSONPath-style path, made up of `.field`, `['field']` and `[index]` steps.
+///
+/// The leading `$` is optional, and the first step must name a column.
+pub fn parse_path(path: &str) -> Result<Vec<PathSegment>, String> {
+    let error = |reason: &str| format!("invalid path `{path}`: {reason}");
+
+    let mut rest = path.trim();
+    let mut segments = vec![];
+    if let Some(stripped) = rest.strip_prefix('$') {
+        rest = stripped;
+    } else if !rest.starts_with(['.', '[']) {
+        // Allow a bare column name, e.g. `customer.address`.
+        let end = rest.find(['.', '[']).unwrap_or(rest.len());
+        if end == 0 {
+            return Err(error("the path is empty"));
+        }
+        segments.push(PathSegment::Field(rest[..end].to_string()));
+        rest = &rest[end..];
+    }
+
+    while !rest.is_empty() {
+        if let Some(stripped) = rest.strip_prefix('.') {
+            let end = stripped.find(['.', '[']).unwrap_or(stripped.len());
+            if end == 0 {
+                return Err(error("expected a field name after `.`"));
+            }
+            segments.push(PathSegment::Field(stripped[..end].to_string()));
+            rest = &stripped[end..];
+        } else if let Some(stripped) = rest.strip_prefix('[') {
+            let end = stripped.find(']').ok_or_else(|| error("unclosed `[`"))?;
+            let inner = stripped[..end].trim();
+            let quoted = ['\'', '"']
+                .iter()
+                .find_map(|&quote| inner.strip_prefix(quote)?.strip_suffix(quote));
+            let segment = match quoted {
+                Some(name) => PathSegment::Field(name.to_string()),
+                None => PathSegment::Index(
+                    inner
+                        .parse()
+                        .map_err(|_| error(&format!("invalid index `{inner}`")))?,
+                ),
+            };
+            segments.push(segment);
+            rest = &stripped[end + 1..];
+        } else {
+            return Err(error("expected `.` or `[`"));
+        }
+    }
+
+    match segments.first() {
+        Some(PathSegment::Field(_)) => Ok(segments),
+        Some(PathSegment::Index(_)) => Err(error("the first step must name a column")),
+        None => Err(error("the path is empty")),
+    }
+}
+
+/// Builds an expression that extracts the value at `path`, which must start with a column.
+pub fn path_expr(path: &[PathSegment]) -> Result<Expr, String> {
+    let mut segments = path.iter();
+    let mut expr = match segments.next() {
+        Some(PathSegment::Field(name)) => Expr::Column(Column::from_name(name)),
+        _ => return Err("the first step of a path must name a column".to_string()),
+    };
+    for segment in segments {
+        expr = match segment {
+            PathSegment::Field(name) => get_field(expr, name.as_str()),
+            // Arrow list indices are one-based, but negative indices are the same.
+            PathSegment::Index(index) if *index >= 0 => array_element(expr, lit(index + 1)),
+            PathSegment::Index(index) => array_element(expr, lit(*index)),
+        };
+    }
+    Ok(expr)
+}
+
+/// Names the column extracted by a path, such as `customer.orders[0].total`, using the same
+/// `parent.child` names as [`flatten_exprs`] whether fields were written as `.field` or
+/// `['field']`.
+pub fn path_name(path: &[PathSegment]) -> String {
+    let mut name = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Field(field) if name.is_empty() => name.push_str(field),
+            PathSegment::Field(field) => {
+                name.push('.');
+                name.push_str(field);
+            }
+            PathSegment::Index(index) => name.push_str(&format!("[{index}]")),
+        }
+    }
+    name
+}
+
+/// Builds a projection that replaces struct columns with one column per field, named
+/// `parent.child`, recursing into at most `depth` levels of structs.
+pub fn flatten_exprs(schema: &DFSchema, depth: usize) -> Vec<Expr> {
+    let mut exprs = vec![];
+    for (qualifier, field) in schema.iter() {
+        let expr = Expr::Column(Column::from((qualifier, field)));
+        flatten_field(
+            expr,
+            field.name().clone(),
+            field.data_type(),
+            depth,
+            &mut exprs,
+        );
+    }
+    exprs
+}
+
+fn flatten_field(
+    expr: Expr,
+    name: String,
+    data_type: &DataType,
+    depth: usize,
+    exprs: &mut Vec<Expr>,
+) {
+    match data_type {
+        DataType::Struct(fields) if depth > 0 && !fields.is_empty() => {
+            for field in fields {
+                let child = get_field(expr.clone(), field.name().as_str());
+                let child_name = format!("{name}.{}", field.name());
+                flatten_field(child, child_name, field.data_type(), depth - 1, exprs);
+            }
+        }
+        _ => exprs.push(expr.alias(name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+
+    use datafusion::arrow::datatypes::{Field, Fields, Schema};
+
+    use super::*;
+
+    fn field(name: &str) -> PathSegment {
+        PathSegment::Field(name.to_string())
+    }
+
+    #[test]
+    fn parses_dotted_paths() {
+        assert_eq!(
+            parse_path("$.customer.address.city"),
+            Ok(vec![field("customer"), field("address"), field("city")])
+        );
+    }
+
+    #[test]
+    fn parses_bare_paths() {
+        assert_eq!(
+            parse_path("customer.name"),
+            Ok(vec![field("customer"), field("name")])
+        );
+    }
+
+    #[test]
+    fn parses_indices_and_quoted_fields() {
+        assert_eq!(
+            parse_path("$.items[0]['unit price'][-1]"),
+            Ok(vec![
+                field("items"),
+                PathSegment::Index(0),
+                field("unit price"),
+                PathSegment::Index(-1),
+            ])
+        );
+    }
+
+    #[test]
+    fn names_paths() {
+        let name = |path| path_name(&parse_path(path).unwrap());
+        assert_eq!(name("$.customer.address"), "customer.address");
+        assert_eq!(name("$['customer']['address']"), "customer.address");
+        assert_eq!(name("$.items[0]['unit price']"), "items[0].unit price");
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        assert!(parse_path("").is_err());
+        assert!(parse_path("   ").is_err());
+        assert!(parse_path("$").is_err());
+        assert!(parse_path("$[0]").is_err());
+        assert!(parse_path("$.a..b").is_err());
+        assert!(parse_path("$.a[x]").is_err());
+        assert!(parse_path("$.a[0").is_err());
+
+        // Paths that weren't parsed don't panic either.
+        assert!(path_expr(&[]).is_err());
+        assert!(path_expr(&[PathSegment::Index(0)]).is_err());
+    }
+
+    #[test]
+    fn flattens_to_depth() {
+        let inner = Fields::from(vec![Field::new("city", DataType::Utf8, true)]);
+        let outer = Fields::from(vec![
+            Field::new("name", DataType::Utf8, true),
+            Field::new("address", DataType::Struct(inner), true),
+        ]);
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("customer", DataType::Struct(outer), true),
+        ]);
+        let schema = DFSchema::try_from(Arc::new(schema)).unwrap();
+
+        let names = |depth| -> Vec<_> {
+            flatten_exprs(&schema, depth)
+                .iter()
+                .map(|expr| expr.schema_name().to_string())
+                .collect()
+        };
+        assert_eq!(names(0), ["id", "customer"]);
+        assert_eq!(names(1), ["id", "customer.name", "customer.address"]);
+        assert_eq!(
+            names(usize::MAX),
+            ["id", "customer.name", "customer.address.city"]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// A least-recently-used cache of encoded pages, keyed by the row range and columns they contain.
+pub struct PageCache<K> {
+    capacity: usize,
+    pages: HashMap<K, Vec<u8>>,
+    /// Keys from least to most recently used.
+    order: VecDeque<K>,
+}
+
+impl<K: Hash + Eq + Clone> PageCache<K> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            pages: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&[u8]> {
+        if self.pages.contains_key(key) {
+            self.touch(key);
+        }
+        self.pages.get(key).map(Vec::as_slice)
+    }
+
+    pub fn insert(&mut self, key: K, page: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.pages.insert(key.clone(), page).is_some() {
+            self.touch(&key);
+            return;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.pages.remove(&evicted);
+            }
+        }
+    }
+
+    fn touch(&mut self, key: &K) {
+        if let Some(index) = self.order.iter().position(|k| k == key) {
+            if let Some(key) = self.order.remove(index) {
+                self.order.push_back(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used_pages() {
+        let mut cache = PageCache::new(2);
+        cache.insert((0, 10), vec![0]);
+        cache.insert((10, 20), vec![1]);
+        assert_eq!(cache.get(&(0, 10)), Some(&[0][..]));
+
+        cache.insert((20, 30), vec![2]);
+        assert_eq!(cache.get(&(10, 20)), None);
+        assert_eq!(cache.get(&(0, 10)), Some(&[0][..]));
+        assert_eq!(cache.get(&(20, 30)), Some(&[2][..]));
+    }
+
+    #[test]
+    fn zero_capacity_caches_nothing() {
+        let mut cache = PageCache::new(0);
+        cache.insert((0, 10), vec![0]);
+        assert_eq!(cache.get(&(0, 10)), None);
+    }
+}
@@ -0,0 +1,443 @@
+use std::convert::TryFrom;
+use std::fmt::Display;
+
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::parquet::basic::Compression;
+use datafusion::parquet::file::metadata::{
+    ColumnChunkMetaData, FooterTail, ParquetMetaDataReader, RowGroupMetaData,
+};
+use datafusion::parquet::file::statistics::{Statistics, ValueStatistics};
+use datafusion::physical_plan::metrics::MetricValue;
+use datafusion::physical_plan::ExecutionPlan;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::file_format::FileFormat;
+
+/// How much of the plan's Parquet files were skipped in its last execution.
+///
+/// Row groups are only checked against statistics and bloom filters when the plan has filters
+/// that can be evaluated on them.
+#[derive(Tsify, Clone, Copy, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct PruningReport {
+    /// The row groups that were checked against their statistics.
+    pub row_groups: usize,
+    /// Row groups skipped because their minimum and maximum values can't match the filters.
+    pub row_groups_pruned_by_statistics: usize,
+    /// Row groups skipped because their bloom filters rule out the filtered values.
+    pub row_groups_pruned_by_bloom_filters: usize,
+    /// Rows skipped because the page index showed their pages can't match the filters.
+    pub rows_pruned_by_page_index: usize,
+    /// Rows that were decoded, then removed by filters evaluated during the scan.
+    pub rows_pruned_by_filters: usize,
+    /// The bytes read from the files, including their metadata.
+    pub bytes_scanned: usize,
+}
+
+/// The footer of a Parquet file, read without reading any of its data.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ParquetMetadata {
+    pub version: i32,
+    pub num_rows: u64,
+    /// The application that wrote the file, such as `parquet-rs version 57.0.0`.
+    #[tsify(optional)]
+    pub created_by: Option<String>,
+    pub key_value_metadata: Vec<KeyValue>,
+    /// The size of the encoded footer, in bytes.
+    pub metadata_size: u64,
+    pub row_groups: Vec<RowGroupMetadata>,
+}
+
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyValue {
+    pub key: String,
+    #[tsify(optional)]
+    pub value: Option<String>,
+}
+
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct RowGroupMetadata {
+    pub num_rows: u64,
+    /// The size of the row group's columns once decompressed.
+    pub total_byte_size: u64,
+    pub compressed_size: u64,
+    pub columns: Vec<ColumnChunkMetadata>,
+}
+
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnChunkMetadata {
+    /// The dotted path of the column, such as `customer.address.city` for nested columns.
+    pub path: String,
+    /// The physical type of the column, such as `INT64` or `BYTE_ARRAY`.
+    pub physical_type: String,
+    /// The encodings used by the column's pages, such as `PLAIN` and `RLE_DICTIONARY`.
+    pub encodings: Vec<String>,
+    /// The compression codec, such as `SNAPPY` or `ZSTD`.
+    pub compression: String,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    #[tsify(optional)]
+    pub statistics: Option<ColumnStatistics>,
+    pub has_bloom_filter: bool,
+    pub has_page_index: bool,
+}
+
+/// Statistics written for a column chunk. Each is omitted if the writer didn't record it.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnStatistics {
+    /// The smallest value, as its physical type. Byte arrays are shown as text if they are valid
+    /// UTF-8, and in hexadecimal otherwise.
+    #[tsify(optional)]
+    pub min: Option<String>,
+    #[tsify(optional)]
+    pub max: Option<String>,
+    #[tsify(optional)]
+    pub null_count: Option<u64>,
+    #[tsify(optional)]
+    pub distinct_count: Option<u64>,
+}
+
+/// The size of the footer at the end of every Parquet file, which holds the metadata's length.
+pub const FOOTER_SIZE: usize = 8;
+
+/// Returns the length of the metadata that precedes the footer, the last 8 bytes of a file.
+pub fn metadata_length(footer: &[u8]) -> Result<usize, String> {
+    let tail = FooterTail::try_from(footer).map_err(|err| err.to_string())?;
+    if tail.is_encrypted_footer() {
+        return Err("cannot read encrypted Parquet metadata".to_string());
+    }
+    Ok(tail.metadata_length())
+}
+
+/// Decodes the metadata of a Parquet file, which immediately precedes its footer.
+pub fn decode_metadata(bytes: &[u8]) -> Result<ParquetMetadata, String> {
+    let metadata = ParquetMetaDataReader::decode_metadata(bytes).map_err(|err| err.to_string())?;
+    let file = metadata.file_metadata();
+    let key_value_metadata = file
+        .key_value_metadata()
+        .into_iter()
+        .flatten()
+        .map(|kv| KeyValue {
+            key: kv.key.clone(),
+            value: kv.value.clone(),
+        })
+        .collect();
+
+    Ok(ParquetMetadata {
+        version: file.version(),
+        num_rows: file.num_rows() as u64,
+        created_by: file.created_by().map(str::to_string),
+        key_value_metadata,
+        metadata_size: bytes.len() as u64,
+        row_groups: metadata.row_groups().iter().map(row_group).collect(),
+    })
+}
+
+fn row_group(row_group: &RowGroupMetaData) -> RowGroupMetadata {
+    RowGroupMetadata {
+        num_rows: row_group.num_rows() as u64,
+        total_byte_size: row_group.total_byte_size() as u64,
+        compressed_size: row_group.compressed_size() as u64,
+        columns: row_group.columns().iter().map(column_chunk).collect(),
+    }
+}
+
+fn column_chunk(column: &ColumnChunkMetaData) -> ColumnChunkMetadata {
+    ColumnChunkMetadata {
+        path: column.column_path().string(),
+        physical_type: format!("{:?}", column.column_type()),
+        encodings: column
+            .encodings()
+            .map(|encoding| format!("{encoding:?}"))
+            .collect(),
+        compression: codec_name(column.compression()).to_string(),
+        compressed_size: column.compressed_size() as u64,
+        uncompressed_size: column.uncompressed_size() as u64,
+        statistics: column.statistics().map(statistics),
+        has_bloom_filter: column.bloom_filter_offset().is_some(),
+        has_page_index: column.column_index_offset().is_some(),
+    }
+}
+
+/// Names a codec without its level, which isn't stored in the file.
+fn codec_name(compression: Compression) -> &'static str {
+    match compression {
+        Compression::UNCOMPRESSED => "UNCOMPRESSED",
+        Compression::SNAPPY => "SNAPPY",
+        Compression::GZIP(_) => "GZIP",
+        Compression::LZO => "LZO",
+        Compression::BROTLI(_) => "BROTLI",
+        Compression::LZ4 => "LZ4",
+        Compression::ZSTD(_) => "ZSTD",
+        Compression::LZ4_RAW => "LZ4_RAW",
+    }
+}
+
+fn statistics(statistics: &Statistics) -> ColumnStatistics {
+    fn min_max<T>(
+        statistics: &ValueStatistics<T>,
+        format: impl Fn(&T) -> String,
+    ) -> (Option<String>, Option<String>) {
+        (
+            statistics.min_opt().map(&format),
+            statistics.max_opt().map(&format),
+        )
+    }
+    fn display<T: Display>(value: &T) -> String {
+        value.to_string()
+    }
+    fn bytes(value: &[u8]) -> String {
+        match std::str::from_utf8(value) {
+            Ok(value) => value.to_string(),
+            Err(_) => value.iter().map(|byte| format!("{byte:02x}")).collect(),
+        }
+    }
+
+    let (min, max) = match statistics {
+        Statistics::Boolean(s) => min_max(s, display),
+        Statistics::Int32(s) => min_max(s, display),
+        Statistics::Int64(s) => min_max(s, display),
+        Statistics::Int96(s) => min_max(s, display),
+        Statistics::Float(s) => min_max(s, display),
+        Statistics::Double(s) => min_max(s, display),
+        Statistics::ByteArray(s) => min_max(s, |value| bytes(value.data())),
+        Statistics::FixedLenByteArray(s) => min_max(s, |value| bytes(value.data())),
+    };
+    ColumnStatistics {
+        min,
+        max,
+        null_count: statistics.null_count_opt(),
+        distinct_count: statistics.distinct_count_opt(),
+    }
+}
+
+/// Creates a Parquet format with the options of `format`, which must be Parquet.
+pub fn parquet_format(format: &FileFormat) -> ParquetFormat {
+    let FileFormat::Parquet {
+        pushdown_filters,
+        page_index,
+        bloom_filters,
+        metadata_size_hint,
+    } = format
+    else {
+        return ParquetFormat::default();
+    };
+
+    let mut options = ParquetFormat::default().options().clone();
+    options.global.pushdown_filters = pushdown_filters.unwrap_or(true);
+    options.global.reorder_filters = options.global.pushdown_filters;
+    options.global.enable_page_index = page_index.unwrap_or(true);
+    options.global.bloom_filter_on_read = bloom_filters.unwrap_or(true);
+    if metadata_size_hint.is_some() {
+        options.global.metadata_size_hint = *metadata_size_hint;
+    }
+    ParquetFormat::default().with_options(options)
+}
+
+/// Sums the pruning metrics of every Parquet scan in an executed plan.
+pub fn pruning_report(plan: &dyn ExecutionPlan) -> PruningReport {
+    let mut report = PruningReport::default();
+    add_metrics(plan, &mut report);
+    report
+}
+
+fn add_metrics(plan: &dyn ExecutionPlan, report: &mut PruningReport) {
+    if let Some(metrics) = plan.metrics() {
+        let sum = |name: &str| match metrics.sum_by_name(name) {
+            Some(MetricValue::Count { count, .. }) => (count.value(), 0),
+            Some(MetricValue::PruningMetrics { pruning_metrics, .. }) => {
+                (pruning_metrics.pruned(), pruning_metrics.matched())
+            }
+            _ => (0, 0),
+        };
+        let (pruned, matched) = sum("row_groups_pruned_statistics");
+        report.row_groups += pruned + matched;
+        report.row_groups_pruned_by_statistics += pruned;
+        report.row_groups_pruned_by_bloom_filters += sum("row_groups_pruned_bloom_filter").0;
+        report.rows_pruned_by_page_index += sum("page_index_rows_pruned").0;
+        report.rows_pruned_by_filters += sum("pushdown_rows_pruned").0;
+        report.bytes_scanned += sum("bytes_scanned").0;
+    }
+    for child in plan.children() {
+        add_metrics(child.as_ref(), report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::StringArray;
+    use datafusion::arrow::array::{Int64Array, RecordBatch};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::datasource::listing::{
+        ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+    };
+    use datafusion::object_store::memory::InMemory;
+    use datafusion::object_store::path::Path;
+    use datafusion::object_store::ObjectStore;
+    use datafusion::parquet::arrow::ArrowWriter;
+    use datafusion::parquet::basic::ZstdLevel;
+    use datafusion::parquet::file::metadata::KeyValue as ParquetKeyValue;
+    use datafusion::parquet::file::properties::WriterProperties;
+    use datafusion::physical_plan::collect;
+    use datafusion::prelude::*;
+    use futures::executor::block_on;
+    use url::Url;
+
+    use super::*;
+
+    /// Writes 1000 rows in row groups of 100 rows, with bloom filters. Column `n` counts from 0
+    /// to 999, and column `m` holds the even numbers below 2000 in a scattered order, so that
+    /// every row group spans almost the whole range.
+    fn parquet_file() -> Vec<u8> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("n", DataType::Int64, false),
+            Field::new("m", DataType::Int64, false),
+        ]));
+        let n = Int64Array::from_iter_values(0..1000);
+        let m = Int64Array::from_iter_values((0..1000).map(|i| i * 37 % 1000 * 2));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(n), Arc::new(m)]).unwrap();
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(100)
+            .set_bloom_filter_enabled(true)
+            .build();
+        let mut writer = ArrowWriter::try_new(vec![], schema, Some(properties)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.into_inner().unwrap()
+    }
+
+    fn scan(format: FileFormat, filter: Expr) -> (usize, PruningReport) {
+        let store = Arc::new(InMemory::new());
+        block_on(store.put(&Path::from("0"), parquet_file().into())).unwrap();
+
+        let config = SessionConfig::new().with_target_partitions(1);
+        let ctx = SessionContext::new_with_config(config);
+        ctx.register_object_store(&Url::parse("memory:///").unwrap(), store);
+        let options =
+            ListingOptions::new(Arc::new(parquet_format(&format))).with_file_extension("");
+        let config = ListingTableConfig::new(ListingTableUrl::parse("memory:///0").unwrap())
+            .with_listing_options(options);
+        let config = block_on(config.infer_schema(&ctx.state())).unwrap();
+        let table = ListingTable::try_new(config).unwrap();
+
+        block_on(async {
+            let df = ctx.read_table(Arc::new(table))?.filter(filter)?;
+            let plan = df.create_physical_plan().await?;
+            let batches = collect(plan.clone(), ctx.task_ctx()).await?;
+            let num_rows = batches.iter().map(|batch| batch.num_rows()).sum();
+            Ok::<_, datafusion::error::DataFusionError>((num_rows, pruning_report(plan.as_ref())))
+        })
+        .unwrap()
+    }
+
+    fn parquet(bloom_filters: Option<bool>) -> FileFormat {
+        FileFormat::Parquet {
+            pushdown_filters: None,
+            page_index: None,
+            bloom_filters,
+            metadata_size_hint: None,
+        }
+    }
+
+    #[test]
+    fn prunes_row_groups_by_statistics() {
+        let (num_rows, report) = scan(parquet(None), col("n").gt_eq(lit(850i64)));
+        assert_eq!(num_rows, 150);
+        assert_eq!(report.row_groups, 10);
+        assert_eq!(report.row_groups_pruned_by_statistics, 8);
+        assert_eq!(report.rows_pruned_by_filters, 50);
+        assert!(report.bytes_scanned > 0);
+    }
+
+    #[test]
+    fn prunes_row_groups_by_bloom_filters() {
+        // 74 is only in the first row group, and odd numbers are in none of them.
+        let (num_rows, report) = scan(parquet(None), col("m").eq(lit(74i64)));
+        assert_eq!(num_rows, 1);
+        assert_eq!(report.row_groups_pruned_by_statistics, 0);
+        assert_eq!(report.row_groups_pruned_by_bloom_filters, 9);
+
+        let (num_rows, report) = scan(parquet(None), col("m").eq(lit(501i64)));
+        assert_eq!(num_rows, 0);
+        assert_eq!(report.row_groups_pruned_by_bloom_filters, 10);
+
+        let (_, report) = scan(parquet(Some(false)), col("m").eq(lit(501i64)));
+        assert_eq!(report.row_groups_pruned_by_bloom_filters, 0);
+    }
+
+    #[test]
+    fn reads_metadata_from_footer() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![3, 1, 2])),
+                Arc::new(StringArray::from(vec![Some("b"), None, Some("a")])),
+            ],
+        )
+        .unwrap();
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(2)
+            .set_compression(Compression::ZSTD(ZstdLevel::try_new(3).unwrap()))
+            .set_key_value_metadata(Some(vec![ParquetKeyValue::new(
+                "origin".to_string(),
+                "test".to_string(),
+            )]))
+            .build();
+        let mut writer = ArrowWriter::try_new(vec![], schema, Some(properties)).unwrap();
+        writer.write(&batch).unwrap();
+        let file = writer.into_inner().unwrap();
+
+        let footer = &file[file.len() - FOOTER_SIZE..];
+        let length = metadata_length(footer).unwrap();
+        let start = file.len() - FOOTER_SIZE - length;
+        let metadata = decode_metadata(&file[start..file.len() - FOOTER_SIZE]).unwrap();
+
+        assert_eq!(metadata.num_rows, 3);
+        assert!(metadata.created_by.unwrap().starts_with("parquet-rs"));
+        assert!(metadata.key_value_metadata.contains(&KeyValue {
+            key: "origin".to_string(),
+            value: Some("test".to_string())
+        }));
+        let rows: Vec<_> = metadata
+            .row_groups
+            .iter()
+            .map(|group| group.num_rows)
+            .collect();
+        assert_eq!(rows, [2, 1]);
+
+        let name = &metadata.row_groups[0].columns[1];
+        assert_eq!(name.path, "name");
+        assert_eq!(name.physical_type, "BYTE_ARRAY");
+        assert_eq!(name.compression, "ZSTD");
+        assert!(name.encodings.contains(&"RLE_DICTIONARY".to_string()));
+        assert_eq!(
+            name.statistics,
+            Some(ColumnStatistics {
+                min: Some("b".to_string()),
+                max: Some("b".to_string()),
+                null_count: Some(1),
+                distinct_count: None,
+            })
+        );
+
+        assert!(metadata_length(&file[..4]).is_err());
+        assert!(metadata_length(&[0; FOOTER_SIZE]).is_err());
+    }
+}
@@ -1,26 +1,126 @@
-use std::convert::TryFrom;
+use std::cell::Cell;
 use std::sync::Arc;
 
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::common::{Column, UnnestOptions};
 use datafusion::datasource::listing::{
     ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
 };
 use datafusion::datasource::provider_as_source;
-use datafusion::execution::TaskContext;
+use datafusion::execution::{SessionState, TaskContext};
 use datafusion::logical_expr::{LogicalPlan, LogicalPlanBuilder, UNNAMED_TABLE};
-use datafusion::physical_plan::collect;
+use datafusion::object_store::ObjectStore;
+use datafusion::physical_plan::{execute_stream, ExecutionPlan};
 use datafusion::prelude::*;
-use datafusion_web_object_store::{HashMapResolver, WebObjectStore};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
 use url::Url;
 use wasm_bindgen::prelude::*;
 
+use crate::compute::Predicate;
+use crate::explain::{explain_logical, explain_physical, Explanation};
+use crate::export::{ExportFormat, Exporter};
 use crate::file_format::FileFormat;
-use crate::record_set::RecordSet;
+use crate::malformed::{MalformedRow, MalformedRowPolicy};
+use crate::memory::{
+    execution_error, Collector, MemoryOptions, MemoryUsage, PeakMemoryPool, SpillStore,
+};
+use crate::metrics::ExecutionMetrics;
+use crate::parquet::{pruning_report, PruningReport};
+use crate::record_set::{ColumnRef, RecordSet};
+use crate::source::{BlobSource, BytesSource, InputSource};
+use crate::utils::{bytes_to_blob_with_type, now_millis};
 use crate::JsSchema;
 
+/// The version of [`SavedPlan`]s written by this version of the engine.
+const SAVED_PLAN_VERSION: u32 = 1;
+
+/// The table id of files without names.
+const DEFAULT_TABLE_ID: &str = "file";
+
+/// A physical plan ready to execute, with the context and memory pool it runs in.
+type Execution = (
+    Arc<dyn ExecutionPlan>,
+    Arc<TaskContext>,
+    Arc<PeakMemoryPool>,
+);
+
+/// A transformation applied by one of [`Plan`]'s methods, so that plans can be built from
+/// messages rather than method calls.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase", tag = "step")]
+pub enum PlanStep {
+    Filter {
+        predicate: Predicate,
+    },
+    Select {
+        columns: Vec<ColumnRef>,
+    },
+    Limit {
+        skip: usize,
+        #[tsify(optional)]
+        fetch: Option<usize>,
+    },
+    Flatten {
+        #[tsify(optional)]
+        depth: Option<usize>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Unnest {
+        columns: Vec<String>,
+        #[tsify(optional)]
+        preserve_nulls: Option<bool>,
+    },
+    Extract {
+        path: String,
+        #[tsify(optional)]
+        alias: Option<String>,
+    },
+    /// Plans a SQL query over the plan, which it refers to as the table `table`. Only rebuilds
+    /// when the engine is built with the `sql` feature.
+    Sql {
+        query: String,
+        table: String,
+    },
+}
+
+/// How a plan was built, so that it can be saved and rebuilt once its file is attached again.
+///
+/// A saved plan is the file that the plan reads and the steps that were applied to it, which are
+/// replayed to rebuild it, rather than the logical plan itself. SQL queries are saved as their
+/// text, so they are planned again against the rebuilt plan.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedPlan {
+    pub version: u32,
+    /// Stands in for the file that the plan reads, so the app can ask for it to be attached.
+    pub table_id: String,
+    pub format: FileFormat,
+    #[tsify(type = "unknown")]
+    pub schema: Schema,
+    #[tsify(optional)]
+    pub on_malformed: Option<MalformedRowPolicy>,
+    pub memory: MemoryOptions,
+    pub steps: Vec<PlanStep>,
+}
+
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct Plan {
     plan: LogicalPlan,
-    files: Arc<[web_sys::Blob]>,
+    /// The object stores that the plan's files are read through, and the URLs they are
+    /// registered at.
+    object_stores: Arc<[(Url, Arc<dyn ObjectStore>)]>,
+    malformed_rows: Arc<[MalformedRow]>,
+    memory: MemoryOptions,
+    /// Where collected batches are spilled, or `None` to keep them in memory, compressed.
+    spill_store: Option<Arc<dyn ObjectStore>>,
+    memory_usage: Cell<Option<MemoryUsage>>,
+    pruning_report: Cell<Option<PruningReport>>,
+    /// How the plan was built.
+    saved: SavedPlan,
 }
 
 #[wasm_bindgen]
@@ -29,14 +129,285 @@ impl Plan {
         file: web_sys::Blob,
         format: FileFormat,
         schema: &JsSchema,
+        on_malformed: Option<MalformedRowPolicy>,
+    ) -> Result<Self, String> {
+        let source = Arc::new(BlobSource::new(file));
+        Self::read_source(source, format, schema.inner().clone(), on_malformed).await
+    }
+
+    /// Rebuilds a plan saved by [`Plan::to_json`], reading its table from `file`.
+    pub async fn from_json(json: String, file: web_sys::Blob) -> Result<Plan, String> {
+        let saved = serde_json::from_str(&json).map_err(|err| err.to_string())?;
+        Self::from_saved(saved, Arc::new(BlobSource::new(file))).await
+    }
+
+    /// Saves how the plan was built as JSON, with its file replaced by its table id.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(&self.saved).map_err(|err| err.to_string())
+    }
+
+    /// Sets the id that the plan's file is saved as. Defaults to the file's name.
+    pub fn with_table_id(self, table_id: String) -> Self {
+        let saved = SavedPlan { table_id, ..self.saved };
+        Self { saved, ..self }
+    }
+
+    /// The rows that were skipped or repaired when reading the plan's files.
+    pub fn malformed_rows(&self) -> Vec<MalformedRow> {
+        self.malformed_rows.to_vec()
+    }
+
+    /// Limits how much memory each execution of the plan may use.
+    pub fn with_memory(self, options: MemoryOptions) -> Self {
+        let saved = SavedPlan { memory: options.clone(), ..self.saved };
+        Self { memory: options, saved, ..self }
+    }
+
+    /// How much memory the last execution of the plan used, if it has been executed.
+    pub fn memory_usage(&self) -> Option<MemoryUsage> {
+        self.memory_usage.get()
+    }
+
+    /// How much of the plan's Parquet files were skipped in its last execution.
+    pub fn pruning_report(&self) -> Option<PruningReport> {
+        self.pruning_report.get()
+    }
+
+    /// Keeps only the rows that match `predicate`.
+    ///
+    /// The filter is pushed down into file scans, so Parquet row groups and pages that can't
+    /// match it are never read.
+    pub fn filter(self, predicate: Predicate) -> Result<Self, String> {
+        let expr = crate::compute::to_expr(&predicate, self.plan.schema())?;
+        let plan = LogicalPlanBuilder::new(self.plan)
+            .filter(expr)
+            .map_err(|err| err.to_string())?
+            .build()
+            .map_err(|err| err.to_string())?;
+        Ok(Self { plan, ..self }.with_step(PlanStep::Filter { predicate }))
+    }
+
+    /// Keeps only the given columns, in the given order, so that the other columns are never
+    /// read from Parquet files.
+    pub fn select(self, columns: Vec<ColumnRef>) -> Result<Self, String> {
+        let schema = self.plan.schema();
+        let exprs = columns
+            .iter()
+            .map(|column| {
+                let index = column.index(schema.as_arrow())?;
+                Ok(Expr::Column(Column::from(schema.qualified_field(index))))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let plan = LogicalPlanBuilder::new(self.plan)
+            .project(exprs)
+            .map_err(|err| err.to_string())?
+            .build()
+            .map_err(|err| err.to_string())?;
+        Ok(Self { plan, ..self }.with_step(PlanStep::Select { columns }))
+    }
+
+    pub fn limit(self, skip: usize, fetch: Option<usize>) -> Result<Self, String> {
+        let plan = LogicalPlanBuilder::new(self.plan)
+            .limit(skip, fetch)
+            .map_err(|err| err.to_string())?
+            .build()
+            .map_err(|err| err.to_string())?;
+        Ok(Self { plan, ..self }.with_step(PlanStep::Limit { skip, fetch }))
+    }
+
+    /// Replaces struct columns with one column per field, named `parent.child`.
+    ///
+    /// Nested structs are flattened up to `depth` levels deep, or fully if `depth` is omitted.
+    pub fn flatten(self, depth: Option<usize>) -> Result<Self, String> {
+        let exprs = crate::nested::flatten_exprs(self.plan.schema(), depth.unwrap_or(usize::MAX));
+        let plan = LogicalPlanBuilder::new(self.plan)
+            .project(exprs)
+            .map_err(|err| err.to_string())?
+            .build()
+            .map_err(|err| err.to_string())?;
+        Ok(Self { plan, ..self }.with_step(PlanStep::Flatten { depth }))
+    }
+
+    /// Expands each element of the given list columns into its own row.
+    pub fn unnest(
+        self,
+        columns: Vec<String>,
+        preserve_nulls: Option<bool>,
     ) -> Result<Self, String> {
-        let files = Arc::new([file]);
+        let step = PlanStep::Unnest { columns: columns.clone(), preserve_nulls };
+        let columns = columns.into_iter().map(Column::from_name).collect();
+        let options = UnnestOptions::new().with_preserve_nulls(preserve_nulls.unwrap_or(true));
+        let plan = LogicalPlanBuilder::new(self.plan)
+            .unnest_columns_with_options(columns, options)
+            .map_err(|err| err.to_string())?
+            .build()
+            .map_err(|err| err.to_string())?;
+        Ok(Self { plan, ..self }.with_step(step))
+    }
+
+    /// Adds a column containing the value at a path such as `$.customer.address.city`.
+    ///
+    /// The column is named `alias`, or after the path if no alias is given.
+    pub fn extract(self, path: String, alias: Option<String>) -> Result<Self, String> {
+        let segments = crate::nested::parse_path(&path)?;
+        let name = alias
+            .clone()
+            .unwrap_or_else(|| crate::nested::path_name(&segments));
+        let expr = crate::nested::path_expr(&segments)?.alias(name);
+
+        let mut exprs: Vec<_> = self
+            .plan
+            .schema()
+            .columns()
+            .into_iter()
+            .map(Expr::Column)
+            .collect();
+        exprs.push(expr);
+        let plan = LogicalPlanBuilder::new(self.plan)
+            .project(exprs)
+            .map_err(|err| err.to_string())?
+            .build()
+            .map_err(|err| err.to_string())?;
+        Ok(Self { plan, ..self }.with_step(PlanStep::Extract { path, alias }))
+    }
+
+    pub async fn collect(&self) -> Result<RecordSet, String> {
+        use futures::TryStreamExt;
+
+        let planning_start = now_millis();
+        let (physical_plan, task_ctx, pool) = self.create_physical_plan().await?;
+        let execution_start = now_millis();
+        let limit = self.memory.limit;
+
+        let schema = physical_plan.schema();
+        let spill_store = self.memory.spill.then(|| match &self.spill_store {
+            Some(store) => SpillStore::External(store.clone()),
+            None => SpillStore::InMemory,
+        });
+        let mut collector = Collector::new(schema.clone(), &(pool.clone() as _), spill_store);
+        let mut stream =
+            execute_stream(physical_plan.clone(), task_ctx).map_err(|err| err.to_string())?;
+        let mut num_batches = 0;
+        let result = async {
+            while let Some(batch) = stream.try_next().await? {
+                num_batches += 1;
+                collector.push(batch).await?;
+            }
+            Ok(())
+        }
+        .await;
+        let spill_count = collector.spill_count();
+        let spilled_bytes = collector.spilled_bytes();
+        let result = match result {
+            Ok(()) => collector.finish().await,
+            Err(err) => Err(err),
+        };
+        self.memory_usage.set(Some(MemoryUsage {
+            peak_bytes: pool.peak(),
+            limit,
+            spill_count,
+            spilled_bytes,
+        }));
+        self.pruning_report
+            .set(Some(pruning_report(physical_plan.as_ref())));
+        let batches = result.map_err(|err| execution_error(err, limit))?;
+
+        let records = RecordSet::new(schema, batches);
+        let metrics = ExecutionMetrics {
+            planning_millis: execution_start - planning_start,
+            execution_millis: now_millis() - execution_start,
+            peak_memory_bytes: pool.peak(),
+            num_batches,
+            output_rows: records.num_rows(),
+            ..Default::default()
+        };
+        Ok(records.with_metrics(metrics.with_scans(physical_plan.as_ref())))
+    }
+
+    /// Describes the plan before and after optimization, and the physical plan that executes it.
+    /// With `verbose`, the text of each plan includes the schema of each node. With `analyze`,
+    /// the plan is executed first and its results discarded, so that the physical plan reports
+    /// the rows, time and bytes scanned of each node.
+    pub async fn explain(&self, verbose: bool, analyze: bool) -> Result<Explanation, String> {
+        use futures::TryStreamExt;
+
+        let pool = Arc::new(PeakMemoryPool::new(self.memory.limit));
+        let state = self.session_state(pool)?;
+        let optimized = state.optimize(&self.plan).map_err(|err| err.to_string())?;
+        let physical_plan = state
+            .query_planner()
+            .create_physical_plan(&optimized, &state)
+            .await
+            .map_err(|err| err.to_string())?;
+
+        if analyze {
+            let task_ctx = Arc::new(TaskContext::from(&state));
+            execute_stream(physical_plan.clone(), task_ctx)
+                .map_err(|err| err.to_string())?
+                .try_for_each(|_| async { Ok(()) })
+                .await
+                .map_err(|err| execution_error(err, self.memory.limit))?;
+        }
+
+        Ok(Explanation {
+            logical: explain_logical(&self.plan, verbose),
+            optimized: explain_logical(&optimized, verbose),
+            physical: explain_physical(physical_plan.as_ref(), verbose, analyze),
+            analyzed: analyze,
+        })
+    }
+
+    /// Executes the plan and writes its results to a file in the given format, one batch at a
+    /// time, without collecting them first.
+    pub async fn export(&self, format: ExportFormat) -> Result<web_sys::Blob, String> {
+        let bytes = self.export_bytes(&format).await?;
+        bytes_to_blob_with_type(&bytes, format.mime_type())
+    }
+}
+
+impl Plan {
+    /// Creates a plan that scans a file with the given format and schema.
+    pub async fn read_source(
+        source: Arc<dyn InputSource>,
+        format: FileFormat,
+        schema: SchemaRef,
+        on_malformed: Option<MalformedRowPolicy>,
+    ) -> Result<Self, String> {
+        let saved = SavedPlan {
+            version: SAVED_PLAN_VERSION,
+            table_id: source
+                .name()
+                .unwrap_or_else(|| DEFAULT_TABLE_ID.to_string()),
+            format: format.clone(),
+            schema: schema.as_ref().clone(),
+            on_malformed,
+            memory: MemoryOptions::default(),
+            steps: vec![],
+        };
+        let policy = on_malformed.unwrap_or_default();
+        // Parquet files aren't cleaned, so they are left to be read in ranges rather than whole.
+        let skips_cleaning =
+            policy == MalformedRowPolicy::FailFast || matches!(format, FileFormat::Parquet { .. });
+        let (source, format, malformed_rows) = if skips_cleaning {
+            (source, format, vec![])
+        } else {
+            let bytes = source.read().await?;
+            match crate::malformed::clean(&bytes, &format, &schema, policy)? {
+                Some(cleaned) => (
+                    Arc::new(BytesSource::new(cleaned.bytes)) as _,
+                    cleaned.format,
+                    cleaned.malformed_rows,
+                ),
+                None => (source, format, vec![]),
+            }
+        };
+
+        let (source, format) = normalize_json(source, format, &schema).await?;
 
         let format: Arc<dyn datafusion::datasource::file_format::FileFormat> = match format {
-            FileFormat::Json { flatten_top_level_arrays, single_field } => {
+            FileFormat::Json { flatten_top_level_arrays, .. } => {
                 let format = datafusion::datasource::file_format::json::JsonFormat::default()
-                    .with_newline_delimited(!flatten_top_level_arrays)
-                    .with_single_field(single_field.is_some());
+                    .with_newline_delimited(!flatten_top_level_arrays);
                 Arc::new(format)
             }
             FileFormat::Csv { has_headers, .. } => {
@@ -44,17 +415,18 @@ impl Plan {
                     .with_has_header(has_headers);
                 Arc::new(format)
             }
-            FileFormat::Parquet => {
-                let format = datafusion::datasource::file_format::parquet::ParquetFormat::default();
-                Arc::new(format)
-            }
+            FileFormat::Parquet { .. } => Arc::new(crate::parquet::parquet_format(&format)),
         };
 
-        let url = "js:///0";
-        let config =
-            ListingTableConfig::new(ListingTableUrl::parse(url).map_err(|err| err.to_string())?)
-                .with_listing_options(ListingOptions::new(format).with_file_extension(""))
-                .with_schema(schema.inner().clone());
+        let (store, path) = source.object_store().await?;
+        let store_url = Url::parse("input://0/").unwrap();
+        let mut url = store_url.clone();
+        url.set_path(path.as_ref());
+        let config = ListingTableConfig::new(
+            ListingTableUrl::try_new(url, None).map_err(|err| err.to_string())?,
+        )
+        .with_listing_options(ListingOptions::new(format).with_file_extension(""))
+        .with_schema(schema);
         let listing_table = Arc::new(ListingTable::try_new(config).map_err(|err| err.to_string())?);
         let source = provider_as_source(listing_table);
 
@@ -63,40 +435,373 @@ impl Plan {
             .build()
             .map_err(|err| err.to_string())?;
 
-        Ok(Plan { plan, files })
+        Ok(Plan {
+            plan,
+            object_stores: Arc::new([(store_url, store)]),
+            malformed_rows: malformed_rows.into(),
+            memory: MemoryOptions::default(),
+            spill_store: None,
+            memory_usage: Cell::new(None),
+            pruning_report: Cell::new(None),
+            saved,
+        })
     }
 
-    pub fn limit(self, skip: usize, fetch: Option<usize>) -> Result<Self, String> {
-        let plan = LogicalPlanBuilder::new(self.plan)
-            .limit(skip, fetch)
-            .map_err(|err| err.to_string())?
-            .build()
+    /// Rebuilds a saved plan, reading its table from `source`.
+    pub async fn from_saved(
+        saved: SavedPlan,
+        source: Arc<dyn InputSource>,
+    ) -> Result<Self, String> {
+        if saved.version > SAVED_PLAN_VERSION {
+            return Err(format!(
+                "the plan was saved by a newer version of the engine (version {})",
+                saved.version
+            ));
+        }
+        let schema = Arc::new(saved.schema);
+        let mut plan = Self::read_source(source, saved.format, schema, saved.on_malformed)
+            .await?
+            .with_table_id(saved.table_id)
+            .with_memory(saved.memory);
+        for step in saved.steps {
+            plan = plan.apply(step).await?;
+        }
+        Ok(plan)
+    }
+
+    /// Spills collected batches to `store` when [`MemoryOptions::spill`] is set, such as one
+    /// backed by the browser's origin private file system. Without one, they are kept in memory,
+    /// compressed. The store isn't saved with the plan.
+    pub fn with_spill_store(self, store: Arc<dyn ObjectStore>) -> Self {
+        Self { spill_store: Some(store), ..self }
+    }
+
+    /// How the plan was built.
+    pub fn saved(&self) -> &SavedPlan {
+        &self.saved
+    }
+
+    /// Applies a step, as if by calling the matching method.
+    pub async fn apply(self, step: PlanStep) -> Result<Self, String> {
+        match step {
+            PlanStep::Filter { predicate } => self.filter(predicate),
+            PlanStep::Select { columns } => self.select(columns),
+            PlanStep::Limit { skip, fetch } => self.limit(skip, fetch),
+            PlanStep::Flatten { depth } => self.flatten(depth),
+            PlanStep::Unnest { columns, preserve_nulls } => self.unnest(columns, preserve_nulls),
+            PlanStep::Extract { path, alias } => self.extract(path, alias),
+            #[cfg(feature = "sql")]
+            PlanStep::Sql { query, table } => self.sql(&query, &table).await,
+            #[cfg(not(feature = "sql"))]
+            PlanStep::Sql { .. } => Err("the engine was built without SQL support".to_string()),
+        }
+    }
+
+    /// Plans a SQL query over the rows of this plan, which it refers to as the table `table`.
+    ///
+    /// Only queries are allowed, so statements that create tables or change settings fail.
+    #[cfg(feature = "sql")]
+    pub async fn sql(self, query: &str, table: &str) -> Result<Self, String> {
+        use datafusion::datasource::ViewTable;
+        use datafusion::execution::context::SQLOptions;
+
+        let ctx = SessionContext::new();
+        let view = ViewTable::new(self.plan.clone(), None);
+        ctx.register_table(table, Arc::new(view))
             .map_err(|err| err.to_string())?;
-        Ok(Self { plan, ..self })
+        let options = SQLOptions::new()
+            .with_allow_ddl(false)
+            .with_allow_dml(false)
+            .with_allow_statements(false);
+        let plan = ctx
+            .sql_with_options(query, options)
+            .await
+            .map_err(|err| err.to_string())?
+            .into_unoptimized_plan();
+        let step = PlanStep::Sql {
+            query: query.to_string(),
+            table: table.to_string(),
+        };
+        Ok(Self { plan, ..self }.with_step(step))
     }
 
-    pub async fn collect(&self) -> Result<RecordSet, String> {
-        let mut files = HashMapResolver::new();
-        for (index, blob) in self.files.iter().enumerate() {
-            files.insert(format!("{index}"), blob.clone());
+    /// Executes the plan and writes its results in the given format, one batch at a time.
+    pub async fn export_bytes(&self, format: &ExportFormat) -> Result<Vec<u8>, String> {
+        use futures::TryStreamExt;
+
+        let (physical_plan, task_ctx, pool) = self.create_physical_plan().await?;
+        let limit = self.memory.limit;
+
+        let mut exporter = Exporter::try_new(physical_plan.schema(), format)?;
+        let mut stream =
+            execute_stream(physical_plan.clone(), task_ctx).map_err(|err| err.to_string())?;
+        let result = async {
+            while let Some(batch) = stream
+                .try_next()
+                .await
+                .map_err(|err| execution_error(err, limit))?
+            {
+                exporter.write(&batch)?;
+            }
+            Ok::<_, String>(())
         }
-        let file_store = Arc::new(WebObjectStore::new(files));
+        .await;
+        self.memory_usage.set(Some(MemoryUsage {
+            peak_bytes: pool.peak(),
+            limit,
+            ..Default::default()
+        }));
+        self.pruning_report
+            .set(Some(pruning_report(physical_plan.as_ref())));
+        result?;
 
-        let state = SessionContext::new().state();
-        let url = Url::try_from("js:///").unwrap();
-        state.runtime_env().register_object_store(&url, file_store);
+        exporter.finish()
+    }
 
+    /// Records a step in the description of how the plan was built.
+    fn with_step(mut self, step: PlanStep) -> Self {
+        self.saved.steps.push(step);
+        self
+    }
+
+    async fn create_physical_plan(&self) -> Result<Execution, String> {
+        let pool = Arc::new(PeakMemoryPool::new(self.memory.limit));
+        let state = self.session_state(pool.clone())?;
         let physical_plan = state
             .create_physical_plan(&self.plan)
             .await
             .map_err(|err| err.to_string())?;
         let task_ctx = Arc::new(TaskContext::from(&state));
 
-        let schema = physical_plan.schema();
-        let batches = collect(physical_plan, task_ctx)
-            .await
-            .map_err(|err| format!("{err:?}"))?;
+        Ok((physical_plan, task_ctx, pool))
+    }
+
+    /// Creates the session that the plan is planned and executed in, with its files' object
+    /// stores registered.
+    fn session_state(&self, pool: Arc<PeakMemoryPool>) -> Result<SessionState, String> {
+        let runtime = crate::memory::runtime_env(pool)?;
+        let state = SessionContext::new_with_config_rt(SessionConfig::new(), runtime).state();
+        for (url, store) in self.object_stores.iter() {
+            state
+                .runtime_env()
+                .register_object_store(url, store.clone());
+        }
+        Ok(state)
+    }
+}
+
+/// Rewrites JSON files that DataFusion cannot read directly, since it only reads files with one
+/// object per line or a single top-level array.
+async fn normalize_json(
+    source: Arc<dyn InputSource>,
+    format: FileFormat,
+    schema: &SchemaRef,
+) -> Result<(Arc<dyn InputSource>, FileFormat), String> {
+    use crate::json_normalize::{to_json_lines, wrap_values};
+
+    let bytes = match &format {
+        FileFormat::Json { single_field: Some(field), .. } => {
+            let field = schema
+                .field_with_name(field)
+                .map_err(|err| err.to_string())?;
+            wrap_values(&source.read().await?, field.name(), field.data_type())?
+        }
+        FileFormat::Json { concatenated: true, .. } => to_json_lines(&source.read().await?)?,
+        _ => return Ok((source, format)),
+    };
+    let format = FileFormat::Json {
+        flatten_top_level_arrays: false,
+        single_field: None,
+        concatenated: false,
+    };
+    Ok((Arc::new(BytesSource::new(bytes)), format))
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::AsArray;
+    use datafusion::arrow::compute::cast;
+    use datafusion::arrow::datatypes::DataType;
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::source::LocalFileSource;
+
+    fn sales_csv() -> Arc<dyn InputSource> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../data/sales.csv");
+        Arc::new(LocalFileSource::new(path).unwrap())
+    }
+
+    fn read_sales_csv() -> Plan {
+        let source = sales_csv();
+        let format = block_on(crate::infer::infer_format(source.as_ref())).unwrap();
+        let (schema, _) = block_on(crate::infer::infer_schema(
+            source.as_ref(),
+            &format,
+            None,
+            false,
+        ))
+        .unwrap();
+        block_on(Plan::read_source(source, format, schema, None)).unwrap()
+    }
+
+    fn read_json(lines: &str) -> Plan {
+        let source = BytesSource::new(lines.as_bytes().to_vec()).with_name("orders.json");
+        let source: Arc<dyn InputSource> = Arc::new(source);
+        let format = block_on(crate::infer::infer_format(source.as_ref())).unwrap();
+        let (schema, _) = block_on(crate::infer::infer_schema(
+            source.as_ref(),
+            &format,
+            None,
+            false,
+        ))
+        .unwrap();
+        block_on(Plan::read_source(source, format, schema, None)).unwrap()
+    }
+
+    fn strings(records: &RecordSet, name: &str) -> Vec<Option<String>> {
+        records
+            .batches()
+            .iter()
+            .flat_map(|batch| {
+                let column = cast(&batch[name], &DataType::Utf8).unwrap();
+                let column = column.as_string::<i32>();
+                column
+                    .iter()
+                    .map(|value| value.map(str::to_string))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unnests_and_extracts_nested_values() {
+        let plan = read_json(concat!(
+            r#"{"id": 1, "items": [{"sku": "a"}, {"sku": "b"}], "customer": {"city": "Oslo"}}"#,
+            "\n",
+            r#"{"id": 2, "items": [{"sku": "c"}], "customer": {"city": "Lima"}}"#,
+            "\n",
+        ));
+
+        let extracted = plan
+            .clone()
+            .extract("$['customer']['city']".to_string(), None)
+            .unwrap()
+            .extract("$.items[1].sku".to_string(), None)
+            .unwrap();
+        let records = block_on(extracted.collect()).unwrap();
+        assert_eq!(
+            strings(&records, "customer.city"),
+            [Some("Oslo".to_string()), Some("Lima".to_string())]
+        );
+        assert_eq!(
+            strings(&records, "items[1].sku"),
+            [Some("b".to_string()), None]
+        );
+
+        let unnested = plan
+            .unnest(vec!["items".to_string()], None)
+            .unwrap()
+            .extract("items.sku".to_string(), Some("sku".to_string()))
+            .unwrap();
+        let records = block_on(unnested.collect()).unwrap();
+        assert_eq!(records.num_rows(), 3);
+        assert_eq!(
+            strings(&records, "sku"),
+            ["a", "b", "c"].map(|sku| Some(sku.to_string()))
+        );
+    }
+
+    #[test]
+    fn reads_local_files() {
+        let plan = read_sales_csv();
+        let records = block_on(plan.collect()).unwrap();
+        assert_eq!(records.num_rows(), 1041);
+
+        let metrics = records.metrics().unwrap();
+        assert_eq!(metrics.output_rows, 1041);
+        assert!(metrics.num_batches > 0);
+        assert_eq!(metrics.file_groups.len(), 1);
+        assert!(metrics.file_groups[0].paths[0].ends_with("sales.csv"));
+        assert_eq!(metrics.rows_scanned, 1041);
+        // CSV scans don't count the bytes they read.
+        assert_eq!(metrics.bytes_scanned, None);
+
+        let plan = plan.limit(10, Some(5)).unwrap();
+        assert_eq!(block_on(plan.collect()).unwrap().num_rows(), 5);
+    }
+
+    #[test]
+    fn saves_and_rebuilds_plans() {
+        let plan = read_sales_csv()
+            .filter(Predicate::Eq {
+                column: ColumnRef::Name("Country".to_string()),
+                value: "Australia".to_string(),
+            })
+            .unwrap()
+            .select(vec![
+                ColumnRef::Name("Owner".to_string()),
+                ColumnRef::Index(2),
+            ])
+            .unwrap()
+            .limit(0, Some(50))
+            .unwrap();
+        let json = plan.to_json().unwrap();
+        // Files are saved by their table id, rather than their path.
+        assert!(!json.contains("/data/"));
+
+        let saved: SavedPlan = serde_json::from_str(&json).unwrap();
+        assert_eq!(saved.table_id, "sales.csv");
+        assert_eq!(saved.steps.len(), 3);
+
+        let rebuilt = block_on(Plan::from_saved(saved.clone(), sales_csv())).unwrap();
+        assert_eq!(rebuilt.saved(), &saved);
+        let records = block_on(rebuilt.collect()).unwrap();
+        assert_eq!(records.num_rows(), 50);
+        let names: Vec<_> = records.schema().fields().iter().map(|f| f.name()).collect();
+        assert_eq!(names, ["Owner", "Quantity Shipped"]);
+
+        let newer = SavedPlan { version: SAVED_PLAN_VERSION + 1, ..saved };
+        assert!(block_on(Plan::from_saved(newer, sales_csv())).is_err());
+    }
+
+    #[cfg(feature = "sql")]
+    #[test]
+    fn saves_and_rebuilds_sql_plans() {
+        let query = "SELECT \"Owner\" FROM sales WHERE \"Country\" = 'Australia'";
+        let plan = block_on(read_sales_csv().sql(query, "sales"))
+            .unwrap()
+            .limit(0, Some(5))
+            .unwrap();
+        let json = plan.to_json().unwrap();
+
+        let saved: SavedPlan = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            saved.steps[0],
+            PlanStep::Sql {
+                query: query.to_string(),
+                table: "sales".to_string(),
+            }
+        );
+        let rebuilt = block_on(Plan::from_saved(saved, sales_csv())).unwrap();
+        let records = block_on(rebuilt.collect()).unwrap();
+        assert_eq!(records.num_rows(), 5);
+        let names: Vec<_> = records.schema().fields().iter().map(|f| f.name()).collect();
+        assert_eq!(names, ["Owner"]);
+    }
+
+    #[test]
+    fn explains_plans() {
+        let plan = read_sales_csv().limit(0, Some(5)).unwrap();
+        let explanation = block_on(plan.explain(false, false)).unwrap();
+        assert!(!explanation.analyzed);
+        assert_eq!(explanation.logical.root.node_type, "Limit");
+        assert!(explanation.physical.text.contains("sales.csv"));
+        assert_eq!(explanation.physical.root.output_rows, None);
 
-        Ok(RecordSet::new(schema, batches))
+        let explanation = block_on(plan.explain(true, true)).unwrap();
+        assert!(explanation.analyzed);
+        assert!(explanation.logical.text.contains("Owner:Utf8"));
+        assert_eq!(explanation.physical.root.output_rows, Some(5));
     }
 }
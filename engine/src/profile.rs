@@ -0,0 +1,453 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use datafusion::arrow::array::{Array, ArrayRef, AsArray, RecordBatch};
+use datafusion::arrow::compute::{cast, sort_to_indices, SortOptions};
+use datafusion::arrow::datatypes::{DataType, Float64Type};
+use datafusion::arrow::row::{RowConverter, SortField};
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+/// Controls which statistics [`profile`] computes.
+#[derive(Tsify, Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileOptions {
+    /// Whether to estimate distinct counts with HyperLogLog, rather than counting them exactly.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub approximate_distinct: bool,
+    /// The number of most common values to list for string columns. Defaults to 10.
+    #[tsify(optional)]
+    pub top_k: Option<usize>,
+    /// The number of equal-width histogram buckets for numeric columns. Defaults to 10.
+    #[tsify(optional)]
+    pub histogram_buckets: Option<usize>,
+    /// The quantiles to compute for numeric columns, between 0 and 1. Defaults to the quartiles.
+    #[tsify(optional)]
+    pub quantiles: Option<Vec<f64>>,
+}
+
+/// Summary statistics for every column of a record set.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub num_rows: usize,
+    pub columns: Vec<ColumnStats>,
+}
+
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnStats {
+    pub name: String,
+    pub data_type: String,
+    pub null_count: usize,
+    /// The number of distinct non-null values, or `None` if the column's values can't be compared.
+    pub distinct_count: Option<usize>,
+    /// Whether `distinct_count` is a HyperLogLog estimate.
+    pub distinct_is_approximate: bool,
+    /// The smallest value that isn't null or NaN, formatted as text, if the column can be sorted.
+    pub min: Option<String>,
+    /// The largest value that isn't null or NaN, formatted as text, if the column can be sorted.
+    pub max: Option<String>,
+    /// Present for numeric columns with at least one value that isn't null or NaN.
+    pub numeric: Option<NumericStats>,
+    /// The most common values of string columns, from most to least common.
+    pub top_values: Vec<ValueCount>,
+}
+
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct NumericStats {
+    pub mean: f64,
+    /// The population standard deviation.
+    pub stddev: f64,
+    pub quantiles: Vec<Quantile>,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct Quantile {
+    pub quantile: f64,
+    pub value: f64,
+}
+
+/// The number of values in `start..end`, or `start..=end` for the last bucket.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramBucket {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Computes summary statistics for each column of `batch`.
+pub fn profile(batch: &RecordBatch, options: &ProfileOptions) -> Result<Profile, String> {
+    let columns = batch
+        .schema()
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, array)| column_stats(field.name(), array, options))
+        .collect::<Result<_, _>>()?;
+    Ok(Profile { num_rows: batch.num_rows(), columns })
+}
+
+fn column_stats(
+    name: &str,
+    array: &ArrayRef,
+    options: &ProfileOptions,
+) -> Result<ColumnStats, String> {
+    let (min, max) = min_max(array)?;
+    let numeric = if array.data_type().is_numeric() {
+        numeric_stats(array, options)?
+    } else {
+        None
+    };
+    let distinct_count = distinct_count(array, options.approximate_distinct);
+    Ok(ColumnStats {
+        name: name.to_string(),
+        data_type: array.data_type().to_string(),
+        null_count: array.null_count(),
+        distinct_count,
+        distinct_is_approximate: options.approximate_distinct && distinct_count.is_some(),
+        min,
+        max,
+        numeric,
+        top_values: top_values(array, options.top_k.unwrap_or(10))?,
+    })
+}
+
+fn min_max(array: &ArrayRef) -> Result<(Option<String>, Option<String>), String> {
+    let options = SortOptions { descending: false, nulls_first: false };
+    let Ok(indices) = sort_to_indices(array, Some(options), None) else {
+        return Ok((None, None));
+    };
+    let num_values = array.len() - array.null_count();
+    if num_values == 0 {
+        return Ok((None, None));
+    }
+
+    // NaN sorts after every number, or before them if negative, so it is left out as it is from
+    // the numeric statistics.
+    let floats = match array.data_type() {
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            Some(cast(array, &DataType::Float64).map_err(|err| err.to_string())?)
+        }
+        _ => None,
+    };
+    let is_nan = |index: usize| {
+        floats
+            .as_ref()
+            .is_some_and(|floats| floats.as_primitive::<Float64Type>().value(index).is_nan())
+    };
+    let mut values = (0..num_values)
+        .map(|position| indices.value(position) as usize)
+        .filter(|&index| !is_nan(index));
+    let Some(first) = values.next() else {
+        return Ok((None, None));
+    };
+    let last = values.next_back().unwrap_or(first);
+
+    let formatter = ArrayFormatter::try_new(array.as_ref(), &FormatOptions::default())
+        .map_err(|err| err.to_string())?;
+    let format = |index: usize| formatter.value(index).to_string();
+    Ok((Some(format(first)), Some(format(last))))
+}
+
+fn distinct_count(array: &ArrayRef, approximate: bool) -> Option<usize> {
+    let converter = RowConverter::new(vec![SortField::new(array.data_type().clone())]).ok()?;
+    let rows = converter
+        .convert_columns(std::slice::from_ref(array))
+        .ok()?;
+    let values = (0..array.len())
+        .filter(|&i| array.is_valid(i))
+        .map(|i| rows.row(i));
+
+    if approximate {
+        let mut sketch = HyperLogLog::new();
+        for row in values {
+            let mut hasher = DefaultHasher::new();
+            row.as_ref().hash(&mut hasher);
+            sketch.insert(hasher.finish());
+        }
+        Some(sketch.estimate())
+    } else {
+        Some(values.collect::<HashSet<_>>().len())
+    }
+}
+
+fn numeric_stats(
+    array: &ArrayRef,
+    options: &ProfileOptions,
+) -> Result<Option<NumericStats>, String> {
+    let floats = cast(array, &DataType::Float64).map_err(|err| err.to_string())?;
+    let mut values: Vec<f64> = floats
+        .as_primitive::<Float64Type>()
+        .iter()
+        .flatten()
+        .filter(|value| !value.is_nan())
+        .collect();
+    if values.is_empty() {
+        return Ok(None);
+    }
+    values.sort_by(f64::total_cmp);
+
+    // Welford's algorithm, which is stable for large values with a small variance.
+    let (mut mean, mut m2) = (0.0, 0.0);
+    for (i, value) in values.iter().enumerate() {
+        let delta = value - mean;
+        mean += delta / (i + 1) as f64;
+        m2 += delta * (value - mean);
+    }
+    let stddev = (m2 / values.len() as f64).sqrt();
+
+    let quantiles = options
+        .quantiles
+        .clone()
+        .unwrap_or_else(|| vec![0.25, 0.5, 0.75])
+        .into_iter()
+        .map(|quantile| Quantile {
+            quantile,
+            value: quantile_of(&values, quantile),
+        })
+        .collect();
+
+    Ok(Some(NumericStats {
+        mean,
+        stddev,
+        quantiles,
+        histogram: histogram(&values, options.histogram_buckets.unwrap_or(10)),
+    }))
+}
+
+/// Linearly interpolates the quantile `q` of sorted, non-empty `values`.
+fn quantile_of(values: &[f64], q: f64) -> f64 {
+    let position = q.clamp(0.0, 1.0) * (values.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    values[lower] + (values[upper] - values[lower]) * (position - lower as f64)
+}
+
+/// Counts sorted, non-empty `values` into equal-width buckets between the smallest and largest.
+fn histogram(values: &[f64], buckets: usize) -> Vec<HistogramBucket> {
+    let (min, max) = (values[0], values[values.len() - 1]);
+    if buckets == 0 {
+        return vec![];
+    }
+    if min == max || !(max - min).is_finite() {
+        return vec![HistogramBucket {
+            start: min,
+            end: max,
+            count: values.len(),
+        }];
+    }
+
+    let width = (max - min) / buckets as f64;
+    let mut counts = vec![0; buckets];
+    for value in values {
+        let bucket = ((value - min) / width) as usize;
+        counts[bucket.min(buckets - 1)] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| HistogramBucket {
+            start: min + width * i as f64,
+            end: if i + 1 == buckets {
+                max
+            } else {
+                min + width * (i + 1) as f64
+            },
+            count,
+        })
+        .collect()
+}
+
+fn top_values(array: &ArrayRef, k: usize) -> Result<Vec<ValueCount>, String> {
+    let is_string = |data_type: &DataType| {
+        matches!(
+            data_type,
+            DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+        )
+    };
+    let string_column = match array.data_type() {
+        DataType::Dictionary(_, values) => is_string(values),
+        data_type => is_string(data_type),
+    };
+    if !string_column || k == 0 {
+        return Ok(vec![]);
+    }
+
+    let strings = cast(array, &DataType::Utf8).map_err(|err| err.to_string())?;
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for value in strings.as_string::<i32>().iter().flatten() {
+        *counts.entry(value).or_default() += 1;
+    }
+
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    Ok(counts
+        .into_iter()
+        .take(k)
+        .map(|(value, count)| ValueCount { value: value.to_string(), count })
+        .collect())
+}
+
+/// Estimates the number of distinct hashes, with a standard error of about 1.6%.
+struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    const PRECISION: u32 = 12;
+
+    fn new() -> Self {
+        Self { registers: vec![0; 1 << Self::PRECISION] }
+    }
+
+    fn insert(&mut self, hash: u64) {
+        let index = (hash >> (64 - Self::PRECISION)) as usize;
+        // The sentinel bit bounds the rank when the remaining bits are all zero.
+        let rest = (hash << Self::PRECISION) | (1 << (Self::PRECISION - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    fn estimate(&self) -> usize {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&rank| 2f64.powi(-(rank as i32)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+
+        // Small cardinalities are estimated more accurately by counting empty registers.
+        let zeros = self.registers.iter().filter(|&&rank| rank == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as usize
+        } else {
+            estimate.round() as usize
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{DictionaryArray, Float64Array, Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{Field, Int32Type, Schema};
+
+    use super::*;
+
+    fn stats(array: ArrayRef, options: &ProfileOptions) -> ColumnStats {
+        let schema = Schema::new(vec![Field::new("a", array.data_type().clone(), true)]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![array]).unwrap();
+        profile(&batch, options).unwrap().columns.remove(0)
+    }
+
+    #[test]
+    fn profiles_numeric_columns() {
+        let values = vec![
+            Some(4.0),
+            None,
+            Some(1.0),
+            Some(f64::NAN),
+            Some(3.0),
+            Some(2.0),
+        ];
+        let options = ProfileOptions {
+            histogram_buckets: Some(2),
+            quantiles: Some(vec![0.0, 0.5]),
+            ..Default::default()
+        };
+        let column = stats(Arc::new(Float64Array::from(values)), &options);
+        assert_eq!(column.null_count, 1);
+        assert_eq!(column.distinct_count, Some(5));
+        assert_eq!(column.min.as_deref(), Some("1.0"));
+        assert_eq!(column.max.as_deref(), Some("4.0"));
+
+        let numeric = column.numeric.unwrap();
+        assert_eq!(numeric.mean, 2.5);
+        assert_eq!(numeric.stddev, 1.25f64.sqrt());
+        let quantiles: Vec<_> = numeric.quantiles.iter().map(|q| q.value).collect();
+        assert_eq!(quantiles, [1.0, 2.5]);
+        let buckets: Vec<_> = numeric
+            .histogram
+            .iter()
+            .map(|b| (b.start, b.end, b.count))
+            .collect();
+        assert_eq!(buckets, [(1.0, 2.5, 2), (2.5, 4.0, 2)]);
+        assert!(column.top_values.is_empty());
+    }
+
+    #[test]
+    fn lists_top_string_values() {
+        let values: DictionaryArray<Int32Type> = vec![
+            Some("b"),
+            Some("a"),
+            None,
+            Some("b"),
+            Some("c"),
+            Some("a"),
+            Some("b"),
+        ]
+        .into_iter()
+        .collect();
+        let options = ProfileOptions { top_k: Some(2), ..Default::default() };
+        let column = stats(Arc::new(values), &options);
+        assert_eq!(column.distinct_count, Some(3));
+        assert_eq!(column.min.as_deref(), Some("a"));
+        assert_eq!(column.max.as_deref(), Some("c"));
+        assert_eq!(column.numeric, None);
+        let top: Vec<_> = column
+            .top_values
+            .iter()
+            .map(|v| (v.value.as_str(), v.count))
+            .collect();
+        assert_eq!(top, [("b", 3), ("a", 2)]);
+    }
+
+    #[test]
+    fn handles_all_null_columns() {
+        let column = stats(
+            Arc::new(StringArray::from(vec![None::<&str>; 3])),
+            &Default::default(),
+        );
+        assert_eq!(column.null_count, 3);
+        assert_eq!(column.distinct_count, Some(0));
+        assert_eq!((column.min, column.max), (None, None));
+        assert!(column.top_values.is_empty());
+    }
+
+    #[test]
+    fn estimates_distinct_counts() {
+        let values: Int64Array = (0..20_000).map(|i| i % 10_000).collect();
+        let options = ProfileOptions {
+            approximate_distinct: true,
+            ..Default::default()
+        };
+        let column = stats(Arc::new(values), &options);
+        assert!(column.distinct_is_approximate);
+        let estimate = column.distinct_count.unwrap() as f64;
+        assert!((estimate - 10_000.0).abs() < 500.0, "{}", estimate);
+    }
+}
@@ -1,13 +1,93 @@
-use datafusion::arrow::array::RecordBatch;
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{RecordBatch, UInt32Array};
+use datafusion::arrow::compute::{filter_record_batch, take_record_batch};
 use datafusion::arrow::datatypes::{Schema, SchemaRef};
-use datafusion::arrow::ipc::writer::CompressionContext;
+use datafusion::arrow::ipc::writer::{
+    CompressionContext, DictionaryTracker, IpcDataGenerator, IpcWriteOptions,
+};
+use datafusion::arrow::ipc::CompressionType;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
 use wasm_bindgen::prelude::*;
 
+use crate::cell::{format_values, CellValue, DisplayOptions, RowValues};
+use crate::compute::{Predicate, SortKey};
+use crate::copy::{CopyColumn, CopyOptions};
+use crate::export::{ExportFormat, Exporter};
+use crate::metrics::ExecutionMetrics;
+use crate::page_cache::PageCache;
+use crate::profile::{Profile, ProfileOptions};
+use crate::search::{SearchOptions, SearchResults};
+use crate::utils::bytes_to_blob_with_type;
+
 #[wasm_bindgen]
 pub struct RecordSet {
     num_rows: usize,
     schema: SchemaRef,
     batches: Vec<RecordBatch>,
+    compression: Option<IpcCompression>,
+    page_cache: RefCell<Option<PageCache<PageKey>>>,
+    stats: Cell<TransferStats>,
+    /// How the rows were collected, if they were collected by executing a plan.
+    metrics: Option<ExecutionMetrics>,
+}
+
+/// The row range and, if projected, the column indices of an encoded page.
+type PageKey = (usize, usize, Option<Vec<usize>>);
+
+/// A column, given by its name or zero-based index.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(untagged)]
+pub enum ColumnRef {
+    Index(usize),
+    Name(String),
+}
+
+impl ColumnRef {
+    /// Resolves the column to its index in `schema`.
+    pub fn index(&self, schema: &Schema) -> Result<usize, String> {
+        match self {
+            ColumnRef::Index(index) if *index < schema.fields().len() => Ok(*index),
+            ColumnRef::Index(index) => Err(format!("column index out of range: {index}")),
+            ColumnRef::Name(name) => schema.index_of(name).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// The codec used to compress the bodies of encoded pages.
+#[derive(Tsify, Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub enum IpcCompression {
+    Lz4,
+    Zstd,
+}
+
+impl From<IpcCompression> for CompressionType {
+    fn from(compression: IpcCompression) -> Self {
+        match compression {
+            IpcCompression::Lz4 => CompressionType::LZ4_FRAME,
+            IpcCompression::Zstd => CompressionType::ZSTD,
+        }
+    }
+}
+
+/// Counts the pages returned by [`RecordSet::encode_rows`].
+#[derive(Tsify, Clone, Copy, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferStats {
+    /// The number of pages that were requested.
+    pub pages: usize,
+    /// The number of pages that were served from the page cache instead of being encoded.
+    pub cache_hits: usize,
+    /// The total size in bytes of every page that was returned.
+    pub bytes_transferred: usize,
+    /// The size in bytes of the most recently returned page.
+    pub last_page_bytes: usize,
 }
 
 impl From<Vec<RecordBatch>> for RecordSet {
@@ -22,26 +102,99 @@ impl From<Vec<RecordBatch>> for RecordSet {
 impl RecordSet {
     pub fn new(schema: SchemaRef, batches: Vec<RecordBatch>) -> Self {
         let num_rows = batches.iter().map(|b| b.num_rows()).sum();
-        Self { num_rows, schema, batches }
+        Self {
+            num_rows,
+            schema,
+            batches,
+            compression: None,
+            page_cache: RefCell::new(None),
+            stats: Cell::default(),
+            metrics: None,
+        }
     }
-}
 
-#[wasm_bindgen]
-impl RecordSet {
-    pub fn empty() -> Result<Self, String> {
-        let schema = Schema::empty().into();
-        let batches = vec![RecordBatch::new_empty(schema)];
-        Ok(batches.into())
+    pub fn with_metrics(self, metrics: ExecutionMetrics) -> Self {
+        Self { metrics: Some(metrics), ..self }
     }
 
-    pub fn num_rows(&self) -> usize {
-        self.num_rows
+    pub fn schema(&self) -> &SchemaRef {
+        &self.schema
     }
 
-    pub fn encode_schema(&self) -> Vec<u8> {
-        use datafusion::arrow::ipc::writer::{
-            write_message, DictionaryTracker, IpcDataGenerator, IpcWriteOptions,
-        };
+    pub fn batches(&self) -> &[RecordBatch] {
+        &self.batches
+    }
+
+    /// Creates a record set with the same encoding settings as this one, but different rows.
+    fn with_batches(&self, batches: Vec<RecordBatch>) -> Self {
+        let page_cache = self
+            .page_cache
+            .borrow()
+            .as_ref()
+            .map(|cache| cache.capacity());
+        Self {
+            compression: self.compression,
+            page_cache: RefCell::new(page_cache.map(PageCache::new)),
+            ..Self::new(self.schema.clone(), batches)
+        }
+    }
+
+    /// Finds the batch containing `row`, and the row's index within it.
+    fn locate(&self, row: usize) -> Result<(&RecordBatch, usize), String> {
+        let mut offset = 0;
+        for batch in &self.batches {
+            if row < offset + batch.num_rows() {
+                return Ok((batch, row - offset));
+            }
+            offset += batch.num_rows();
+        }
+        Err(format!("row index out of range: {row}"))
+    }
+
+    fn concat_batches(&self) -> Result<RecordBatch, String> {
+        datafusion::arrow::compute::concat_batches(&self.schema, &self.batches)
+            .map_err(|err| err.to_string())
+    }
+
+    fn write_options(&self) -> IpcWriteOptions {
+        use datafusion::arrow::ipc::writer::DictionaryHandling;
+
+        IpcWriteOptions::default()
+            .with_dictionary_handling(DictionaryHandling::Delta)
+            .try_with_compression(self.compression.map(Into::into))
+            .expect("compression is supported by the default metadata version")
+    }
+
+    /// Resolves column names and indices to indices into the schema.
+    fn projection(&self, columns: &[ColumnRef]) -> Result<Vec<usize>, String> {
+        columns
+            .iter()
+            .map(|column| column.index(&self.schema))
+            .collect()
+    }
+
+    fn projected_schema(&self, projection: Option<&[usize]>) -> SchemaRef {
+        match projection {
+            Some(projection) => Arc::new(self.schema.project(projection).unwrap()),
+            None => self.schema.clone(),
+        }
+    }
+
+    /// Creates a dictionary tracker that assigns dictionary ids in the same order as the message
+    /// from [`RecordSet::encode_schema`], so that dictionary batches can be decoded against it.
+    fn dictionary_tracker(schema: &Schema, opts: &IpcWriteOptions) -> DictionaryTracker {
+        let mut tracker = DictionaryTracker::new(false);
+        IpcDataGenerator::default().schema_to_bytes_with_dictionary_tracker(
+            schema,
+            &mut tracker,
+            opts,
+        );
+        tracker
+    }
+
+    /// Encodes a schema as an IPC message, to be read before the pages of rows with that schema.
+    pub fn schema_message(schema: &Schema) -> Vec<u8> {
+        use datafusion::arrow::ipc::writer::write_message;
 
         let mut buffer = vec![];
         let generator = IpcDataGenerator::default();
@@ -49,23 +202,41 @@ impl RecordSet {
         let opts = IpcWriteOptions::default();
 
         let encoded =
-            generator.schema_to_bytes_with_dictionary_tracker(&self.schema, &mut tracker, &opts);
+            generator.schema_to_bytes_with_dictionary_tracker(schema, &mut tracker, &opts);
         write_message(&mut buffer, encoded, &opts).unwrap();
 
         buffer
     }
 
-    pub fn encode_rows(&self, start: usize, end: usize) -> Vec<u8> {
-        use datafusion::arrow::ipc::writer::{
-            write_message, DictionaryTracker, IpcDataGenerator, IpcWriteOptions,
-        };
+    fn page(&self, start: usize, end: usize, projection: Option<Vec<usize>>) -> Vec<u8> {
+        let key = (start, end, projection);
+        let cached = self
+            .page_cache
+            .borrow_mut()
+            .as_mut()
+            .and_then(|cache| cache.get(&key).map(<[u8]>::to_vec));
+        let cache_hit = cached.is_some();
+        let page = cached.unwrap_or_else(|| {
+            let page = self.encode_page(start, end, key.2.as_deref());
+            if let Some(cache) = self.page_cache.borrow_mut().as_mut() {
+                cache.insert(key, page.clone());
+            }
+            page
+        });
 
-        let mut buffer = vec![];
-        let generator = IpcDataGenerator::default();
-        let mut tracker = DictionaryTracker::new(false);
-        let opts = IpcWriteOptions::default();
-        let mut compression = CompressionContext::default();
+        let stats = self.stats.get();
+        self.stats.set(TransferStats {
+            pages: stats.pages + 1,
+            cache_hits: stats.cache_hits + cache_hit as usize,
+            bytes_transferred: stats.bytes_transferred + page.len(),
+            last_page_bytes: page.len(),
+        });
 
+        page
+    }
+
+    /// Slices the batches down to the rows in `start..end`, which is empty if `end < start`.
+    fn slice_rows(&self, start: usize, end: usize) -> impl Iterator<Item = RecordBatch> + '_ {
         self.batches
             .iter()
             .scan(0, |offset, batch| {
@@ -73,20 +244,459 @@ impl RecordSet {
                 *offset += batch.num_rows();
                 Some(output)
             })
-            .skip_while(|(offset, batch)| start >= offset + batch.num_rows())
-            .map_while(|(offset, batch)| {
+            .skip_while(move |(offset, batch)| start >= offset + batch.num_rows())
+            .map_while(move |(offset, batch)| {
                 let i = start.saturating_sub(offset);
-                let j = end.checked_sub(offset)?.min(batch.num_rows());
+                let j = end.checked_sub(offset)?.min(batch.num_rows()).max(i);
                 Some(batch.slice(i, j - i))
             })
+    }
+
+    fn encode_page(&self, start: usize, end: usize, projection: Option<&[usize]>) -> Vec<u8> {
+        use datafusion::arrow::ipc::writer::write_message;
+
+        let mut buffer = vec![];
+        let generator = IpcDataGenerator::default();
+        let opts = self.write_options();
+        let mut tracker = Self::dictionary_tracker(&self.projected_schema(projection), &opts);
+        let mut compression = CompressionContext::default();
+
+        self.slice_rows(start, end)
+            .map(|batch| match projection {
+                Some(projection) => batch.project(projection).unwrap(),
+                None => batch,
+            })
             .for_each(|batch| {
                 let (dicts, batch) = generator
                     .encode(&batch, &mut tracker, &opts, &mut compression)
                     .unwrap();
-                assert!(dicts.is_empty());
+                for dict in dicts {
+                    write_message(&mut buffer, dict, &opts).unwrap();
+                }
                 write_message(&mut buffer, batch, &opts).unwrap();
             });
 
         buffer
     }
 }
+
+#[wasm_bindgen]
+impl RecordSet {
+    pub fn empty() -> Result<Self, String> {
+        let schema = Schema::empty().into();
+        let batches = vec![RecordBatch::new_empty(schema)];
+        Ok(batches.into())
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn encode_schema(&self) -> Vec<u8> {
+        Self::schema_message(&self.schema)
+    }
+
+    /// Encodes a schema message containing only the given columns, in the given order, to be read
+    /// with the pages from [`RecordSet::encode_rows_projected`].
+    pub fn encode_schema_projected(&self, columns: Vec<ColumnRef>) -> Result<Vec<u8>, String> {
+        let projection = self.projection(&columns)?;
+        Ok(Self::schema_message(
+            &self.projected_schema(Some(&projection)),
+        ))
+    }
+
+    /// Encodes the rows in `start..end` as IPC messages, to be read after the schema message.
+    ///
+    /// Each range is self-contained: dictionary-encoded columns are preceded by their full
+    /// dictionaries, followed by a delta or replacement whenever a later batch in the range
+    /// uses a different dictionary.
+    pub fn encode_rows(&self, start: usize, end: usize) -> Vec<u8> {
+        self.page(start, end, None)
+    }
+
+    /// Like [`RecordSet::encode_rows`], but encodes only the given columns, to be read after the
+    /// message from [`RecordSet::encode_schema_projected`] with the same columns.
+    pub fn encode_rows_projected(
+        &self,
+        start: usize,
+        end: usize,
+        columns: Vec<ColumnRef>,
+    ) -> Result<Vec<u8>, String> {
+        let projection = self.projection(&columns)?;
+        Ok(self.page(start, end, Some(projection)))
+    }
+
+    /// Compresses the bodies of pages returned by [`RecordSet::encode_rows`] with the given codec,
+    /// or leaves them uncompressed if `compression` is omitted.
+    pub fn with_compression(self, compression: Option<IpcCompression>) -> Self {
+        Self {
+            compression,
+            page_cache: RefCell::new(
+                self.page_cache
+                    .take()
+                    .map(|cache| PageCache::new(cache.capacity())),
+            ),
+            ..self
+        }
+    }
+
+    /// Keeps up to `capacity` encoded pages, so that scrolling back to a row range that was
+    /// recently fetched does not encode it again.
+    pub fn with_page_cache(self, capacity: usize) -> Self {
+        Self {
+            page_cache: RefCell::new(Some(PageCache::new(capacity))),
+            ..self
+        }
+    }
+
+    pub fn transfer_stats(&self) -> TransferStats {
+        self.stats.get()
+    }
+
+    /// The time, rows, bytes and memory it took to collect the record set from a plan.
+    pub fn metrics(&self) -> Option<ExecutionMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Returns a copy of the rows, sorted by the given columns in order of priority.
+    pub fn sort(&self, keys: Vec<SortKey>) -> Result<RecordSet, String> {
+        let batch = self.concat_batches()?;
+        let indices = crate::compute::sort_indices(&batch, &keys)?;
+        let batch = take_record_batch(&batch, &indices).map_err(|err| err.to_string())?;
+        Ok(self.with_batches(vec![batch]))
+    }
+
+    /// Returns a copy of the rows that meet `predicate`.
+    pub fn filter(&self, predicate: Predicate) -> Result<RecordSet, String> {
+        let batches = self
+            .batches
+            .iter()
+            .map(|batch| {
+                let mask = crate::compute::evaluate(&predicate, batch)?;
+                filter_record_batch(batch, &mask).map_err(|err| err.to_string())
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(self.with_batches(batches))
+    }
+
+    /// Returns the rows at the given indices, in the order given.
+    pub fn take(&self, indices: Vec<u32>) -> Result<RecordSet, String> {
+        if let Some(index) = indices.iter().find(|&&i| i as usize >= self.num_rows) {
+            return Err(format!("row index out of range: {index}"));
+        }
+        let batch = self.concat_batches()?;
+        let batch = take_record_batch(&batch, &UInt32Array::from(indices))
+            .map_err(|err| err.to_string())?;
+        Ok(self.with_batches(vec![batch]))
+    }
+
+    /// Returns the values of a row, in schema order.
+    pub fn row(&self, row: usize) -> Result<RowValues, String> {
+        let (batch, index) = self.locate(row)?;
+        Ok(RowValues(crate::cell::json_values(&batch.slice(index, 1))?))
+    }
+
+    pub fn cell(&self, row: usize, column: ColumnRef) -> Result<CellValue, String> {
+        let (batch, index) = self.locate(row)?;
+        let batch = batch
+            .slice(index, 1)
+            .project(&[column.index(&self.schema)?])
+            .map_err(|err| err.to_string())?;
+        let value = crate::cell::json_values(&batch)?.remove(0);
+        Ok(CellValue(value))
+    }
+
+    /// Formats each value of a row as text, in schema order.
+    pub fn format_row(
+        &self,
+        row: usize,
+        options: Option<DisplayOptions>,
+    ) -> Result<Vec<String>, String> {
+        let (batch, index) = self.locate(row)?;
+        let options = options.unwrap_or_default();
+        batch
+            .columns()
+            .iter()
+            .map(|array| Ok(format_values(&array.slice(index, 1), &options)?.remove(0)))
+            .collect()
+    }
+
+    pub fn format_cell(
+        &self,
+        row: usize,
+        column: ColumnRef,
+        options: Option<DisplayOptions>,
+    ) -> Result<String, String> {
+        let (batch, index) = self.locate(row)?;
+        let array = batch.column(column.index(&self.schema)?).slice(index, 1);
+        Ok(format_values(&array, &options.unwrap_or_default())?.remove(0))
+    }
+
+    /// Renders the rows in `start_row..end_row` and columns in `start_column..end_column` as
+    /// text, for pasting into spreadsheets, documents or chat.
+    pub fn copy_range(
+        &self,
+        start_row: usize,
+        end_row: usize,
+        start_column: usize,
+        end_column: usize,
+        options: CopyOptions,
+    ) -> Result<String, String> {
+        if start_row > end_row {
+            return Err(format!("invalid row range: {start_row}..{end_row}"));
+        }
+        let end_column = end_column.min(self.schema.fields().len());
+        let start_column = start_column.min(end_column);
+        let display = options.display.unwrap_or_default();
+        let mut columns: Vec<_> = self.schema.fields()[start_column..end_column]
+            .iter()
+            .map(|field| CopyColumn {
+                name: field.name().clone(),
+                numeric: field.data_type().is_numeric(),
+                values: vec![],
+            })
+            .collect();
+        for batch in self.slice_rows(start_row, end_row) {
+            for (column, array) in columns.iter_mut().zip(&batch.columns()[start_column..]) {
+                column.values.extend(format_values(array, &display)?);
+            }
+        }
+
+        let include_headers = options.include_headers.unwrap_or(true);
+        Ok(crate::copy::render(
+            &columns,
+            options.format,
+            include_headers,
+        ))
+    }
+
+    /// Finds the cells whose formatted values match `query`, in row-major order.
+    ///
+    /// Results are returned a page at a time. Pass the last hit as `options.after` to fetch the
+    /// next page.
+    pub fn search(
+        &self,
+        query: String,
+        options: Option<SearchOptions>,
+    ) -> Result<SearchResults, String> {
+        let options = options.unwrap_or_default();
+        crate::search::search(&self.schema, &self.batches, &query, &options)
+    }
+
+    /// Computes summary statistics for each column, such as for histograms in the table header.
+    pub fn profile(&self, options: Option<ProfileOptions>) -> Result<Profile, String> {
+        crate::profile::profile(&self.concat_batches()?, &options.unwrap_or_default())
+    }
+
+    /// Writes every row to a file in the given format, for use as a download.
+    pub fn export(&self, format: ExportFormat) -> Result<web_sys::Blob, String> {
+        let mut exporter = Exporter::try_new(self.schema.clone(), &format)?;
+        for batch in &self.batches {
+            exporter.write(batch)?;
+        }
+        bytes_to_blob_with_type(&exporter.finish()?, format.mime_type())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Array, ArrayRef, DictionaryArray, Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Int32Type};
+    use datafusion::arrow::ipc::reader::StreamReader;
+
+    use super::*;
+    use crate::copy::CopyFormat;
+
+    fn record_set(dictionaries: &[&[&str]]) -> RecordSet {
+        let dict_type = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("category", dict_type, true),
+        ]));
+        let batches = dictionaries
+            .iter()
+            .map(|values| {
+                let ids: Vec<i64> = (0..values.len() as i64).collect();
+                let category: DictionaryArray<Int32Type> = values.iter().copied().collect();
+                let columns: Vec<ArrayRef> =
+                    vec![Arc::new(Int64Array::from(ids)), Arc::new(category)];
+                RecordBatch::try_new(schema.clone(), columns).unwrap()
+            })
+            .collect();
+        RecordSet::new(schema, batches)
+    }
+
+    fn decode(record_set: &RecordSet, start: usize, end: usize) -> Vec<RecordBatch> {
+        let mut bytes = record_set.encode_schema();
+        bytes.extend(record_set.encode_rows(start, end));
+        StreamReader::try_new(std::io::Cursor::new(bytes), None)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn encodes_dictionary_columns() {
+        let record_set = record_set(&[&["a", "b", "a"], &["a", "b", "c"], &["x", "y"]]);
+        let batches = decode(&record_set, 0, 8);
+        assert_eq!(batches, record_set.batches);
+    }
+
+    #[test]
+    fn each_range_includes_its_dictionaries() {
+        let record_set = record_set(&[&["a", "b", "a"], &["x", "y"]]);
+        let batches = decode(&record_set, 4, 5);
+        assert_eq!(batches, [record_set.batches[1].slice(1, 1)]);
+    }
+
+    #[test]
+    fn compresses_pages() {
+        for compression in [IpcCompression::Lz4, IpcCompression::Zstd].iter() {
+            let record_set =
+                record_set(&[&["a", "b", "a"], &["x", "y"]]).with_compression(Some(*compression));
+            let batches = decode(&record_set, 0, 5);
+            assert_eq!(batches, record_set.batches);
+        }
+    }
+
+    #[test]
+    fn caches_pages_and_counts_bytes() {
+        let record_set = record_set(&[&["a", "b", "a"], &["x", "y"]]).with_page_cache(1);
+        let page = record_set.encode_rows(0, 3);
+        assert_eq!(record_set.encode_rows(0, 3), page);
+        record_set.encode_rows(3, 5);
+        let last = record_set.encode_rows(0, 3);
+
+        let stats = record_set.transfer_stats();
+        assert_eq!(stats.pages, 4);
+        assert_eq!(stats.cache_hits, 1);
+        assert_eq!(stats.last_page_bytes, last.len());
+        assert_eq!(
+            stats.bytes_transferred,
+            page.len() * 3 + record_set.encode_page(3, 5, None).len()
+        );
+    }
+
+    #[test]
+    fn projects_columns_by_name_or_index() {
+        let record_set = record_set(&[&["a", "b", "a"], &["x", "y"]]).with_page_cache(4);
+        let columns = || vec![ColumnRef::Name("category".to_string()), ColumnRef::Index(0)];
+
+        let mut bytes = record_set.encode_schema_projected(columns()).unwrap();
+        bytes.extend(record_set.encode_rows_projected(2, 4, columns()).unwrap());
+        let batches: Vec<_> = StreamReader::try_new(std::io::Cursor::new(bytes), None)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        let expected: Vec<_> = record_set.batches[..]
+            .iter()
+            .map(|batch| batch.project(&[1, 0]).unwrap())
+            .collect();
+        assert_eq!(batches, [expected[0].slice(2, 1), expected[1].slice(0, 1)]);
+
+        // Projected and full pages of the same range are cached separately.
+        assert_ne!(
+            record_set.encode_rows(2, 4),
+            record_set.encode_rows_projected(2, 4, columns()).unwrap()
+        );
+        assert_eq!(record_set.transfer_stats().cache_hits, 1);
+    }
+
+    #[test]
+    fn rejects_unknown_columns() {
+        let record_set = record_set(&[&["a"]]);
+        let missing = ColumnRef::Name("missing".to_string());
+        assert!(record_set.encode_schema_projected(vec![missing]).is_err());
+        assert!(record_set
+            .encode_rows_projected(0, 1, vec![ColumnRef::Index(2)])
+            .is_err());
+    }
+
+    #[test]
+    fn sorts_filters_and_takes_rows() {
+        let record_set = record_set(&[&["b", "c", "a"], &["a", "d"]]).with_page_cache(4);
+        let category = || ColumnRef::Name("category".to_string());
+        let values = |record_set: RecordSet| -> Vec<String> {
+            let batch = record_set.concat_batches().unwrap();
+            let column = datafusion::arrow::compute::cast(batch.column(1), &DataType::Utf8);
+            let column = column.unwrap();
+            let column = column.as_any().downcast_ref::<StringArray>().unwrap();
+            column
+                .iter()
+                .map(|value| value.unwrap().to_string())
+                .collect()
+        };
+
+        let key = SortKey {
+            column: category(),
+            descending: false,
+            nulls_first: false,
+        };
+        let sorted = record_set.sort(vec![key]).unwrap();
+        assert!(sorted.page_cache.borrow().is_some());
+        assert_eq!(values(sorted), ["a", "a", "b", "c", "d"]);
+
+        let predicate = Predicate::GtEq {
+            column: category(),
+            value: "c".to_string(),
+        };
+        let filtered = record_set.filter(predicate).unwrap();
+        assert_eq!(filtered.num_rows(), 2);
+        assert_eq!(values(filtered), ["c", "d"]);
+
+        assert_eq!(
+            values(record_set.take(vec![4, 0, 0]).unwrap()),
+            ["d", "b", "b"]
+        );
+        assert!(record_set.take(vec![5]).is_err());
+    }
+
+    #[test]
+    fn looks_up_rows_and_cells() {
+        let record_set = record_set(&[&["a", "b", "a"], &["x", "y"]]);
+        let category = || ColumnRef::Name("category".to_string());
+
+        let row = record_set.row(4).unwrap();
+        assert_eq!(row.0, [serde_json::json!(1), serde_json::json!("y")]);
+        let cell = record_set.cell(3, category()).unwrap();
+        assert_eq!(cell.0, serde_json::json!("x"));
+        assert!(record_set.row(5).is_err());
+
+        assert_eq!(record_set.format_row(1, None).unwrap(), ["1", "b"]);
+        let cell = record_set.format_cell(2, ColumnRef::Index(0), None);
+        assert_eq!(cell.unwrap(), "2");
+    }
+
+    #[test]
+    fn copies_ranges() {
+        let record_set = record_set(&[&["a", "b", "a"], &["x", "y"]]);
+        let options = |format| CopyOptions {
+            format,
+            include_headers: None,
+            display: None,
+        };
+
+        let tsv = record_set.copy_range(2, 4, 0, 2, options(CopyFormat::Tsv));
+        assert_eq!(tsv.unwrap(), "id\tcategory\n2\ta\n0\tx\n");
+        let markdown = record_set.copy_range(1, 2, 1, 5, options(CopyFormat::Markdown));
+        assert_eq!(markdown.unwrap(), "| category |\n| --- |\n| b |\n");
+    }
+
+    #[test]
+    fn rejects_reversed_row_ranges() {
+        let record_set = record_set(&[&["a", "b", "a"], &["x", "y"]]);
+        let options = CopyOptions {
+            format: CopyFormat::Tsv,
+            include_headers: None,
+            display: None,
+        };
+        assert!(record_set.copy_range(2, 1, 0, 2, options).is_err());
+        for (start, end) in [(2, 1), (4, 3)].iter() {
+            let batches = decode(&record_set, *start, *end);
+            assert!(batches.iter().all(|batch| batch.num_rows() == 0));
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::LazyLock;
+
+use datafusion::arrow::datatypes::{DataType, Schema};
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+use regex::RegexSet;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tsify::Tsify;
+
+use crate::file_format::FileFormat;
+
+/// The maximum number of example values kept for each column.
+const MAX_EXAMPLES: usize = 5;
+
+/// The maximum number of widening rows recorded for each column.
+const MAX_WIDENING_ROWS: usize = 10;
+
+/// Explains how the schema of a file was inferred.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaReport {
+    /// The number of records that were sampled.
+    pub records_sampled: usize,
+    /// One entry per column of the inferred schema, in schema order.
+    pub columns: Vec<ColumnReport>,
+}
+
+/// Explains how the type of a single column was inferred.
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnReport {
+    pub name: String,
+    /// The type that was chosen for the column.
+    pub data_type: String,
+    /// The number of sampled records where the column was null or missing.
+    pub null_count: usize,
+    /// The number of distinct non-null values seen in the sample.
+    pub distinct_count: usize,
+    /// The first few distinct non-null values, formatted as text.
+    pub examples: Vec<String>,
+    /// Every type that at least one sampled value could be read as, in order of first appearance.
+    pub candidate_types: Vec<String>,
+    /// Zero-based indices of the first records whose value could only be read as `Utf8`,
+    /// forcing the column to be widened. Empty unless other candidate types were seen.
+    pub widening_rows: Vec<usize>,
+}
+
+/// Builds a report for a file whose schema has already been inferred.
+pub async fn build_report(
+    bytes: Vec<u8>,
+    format: &FileFormat,
+    schema: &Schema,
+    max_records: Option<usize>,
+) -> Result<SchemaReport, String> {
+    let max_records = max_records.unwrap_or(usize::MAX);
+    let mut profiles: Vec<_> = schema
+        .fields()
+        .iter()
+        .map(|_| ColumnProfile::default())
+        .collect();
+
+    let records_sampled = match format {
+        FileFormat::Csv { has_headers, .. } => {
+            sample_csv(&bytes, *has_headers, max_records, &mut profiles)?
+        }
+        FileFormat::Json {
+            flatten_top_level_arrays, single_field, ..
+        } => sample_json(
+            &bytes,
+            *flatten_top_level_arrays,
+            single_field.as_deref(),
+            schema,
+            max_records,
+            &mut profiles,
+        )?,
+        FileFormat::Parquet { .. } => sample_parquet(bytes, max_records, &mut profiles).await?,
+    };
+
+    let columns = schema
+        .fields()
+        .iter()
+        .zip(profiles)
+        .map(|(field, profile)| profile.finish(field.name(), field.data_type()))
+        .collect();
+
+    Ok(SchemaReport { records_sampled, columns })
+}
+
+fn sample_csv(
+    bytes: &[u8],
+    has_headers: bool,
+    max_records: usize,
+    profiles: &mut [ColumnProfile],
+) -> Result<usize, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(has_headers)
+        .from_reader(bytes);
+
+    let mut record = csv::StringRecord::new();
+    let mut row = 0;
+    while row < max_records {
+        if !reader
+            .read_record(&mut record)
+            .map_err(|err| err.to_string())?
+        {
+            break;
+        }
+        for (column, profile) in profiles.iter_mut().enumerate() {
+            match record.get(column).filter(|value| !value.is_empty()) {
+                Some(value) => profile.observe(row, value, classify_csv(value)),
+                None => profile.observe_null(),
+            }
+        }
+        row += 1;
+    }
+
+    Ok(row)
+}
+
+fn sample_json(
+    bytes: &[u8],
+    flatten_top_level_arrays: bool,
+    single_field: Option<&str>,
+    schema: &Schema,
+    max_records: usize,
+    profiles: &mut [ColumnProfile],
+) -> Result<usize, String> {
+    let mut row = 0;
+    for value in serde_json::Deserializer::from_slice(bytes).into_iter::<Value>() {
+        let records = match value.map_err(|err| err.to_string())? {
+            Value::Array(items) if flatten_top_level_arrays => items,
+            value => vec![value],
+        };
+        for value in records {
+            if row >= max_records {
+                return Ok(row);
+            }
+            let record = match single_field {
+                Some(field) => std::iter::once((field.to_string(), value)).collect(),
+                None => match value {
+                    Value::Object(record) => record,
+                    _ => Default::default(),
+                },
+            };
+            for (field, profile) in schema.fields().iter().zip(profiles.iter_mut()) {
+                match record.get(field.name()) {
+                    None | Some(Value::Null) => profile.observe_null(),
+                    Some(Value::String(value)) => profile.observe(row, value, "Utf8"),
+                    Some(value) => profile.observe(row, &value.to_string(), classify_json(value)),
+                }
+            }
+            row += 1;
+        }
+    }
+
+    Ok(row)
+}
+
+async fn sample_parquet(
+    bytes: Vec<u8>,
+    max_records: usize,
+    profiles: &mut [ColumnProfile],
+) -> Result<usize, String> {
+    use datafusion::parquet::arrow::ParquetRecordBatchStreamBuilder;
+    use futures::TryStreamExt;
+
+    let batches: Vec<_> = ParquetRecordBatchStreamBuilder::new(std::io::Cursor::new(bytes))
+        .await
+        .map_err(|err| err.to_string())?
+        .with_limit(max_records)
+        .build()
+        .map_err(|err| err.to_string())?
+        .try_collect()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let options = FormatOptions::default();
+    let mut offset = 0;
+    for batch in &batches {
+        for (array, profile) in batch.columns().iter().zip(profiles.iter_mut()) {
+            let data_type = array.data_type().to_string();
+            let formatter =
+                ArrayFormatter::try_new(array, &options).map_err(|err| err.to_string())?;
+            for index in 0..array.len() {
+                if array.is_null(index) {
+                    profile.observe_null();
+                } else {
+                    let value = formatter.value(index).to_string();
+                    profile.observe(offset + index, &value, &data_type);
+                }
+            }
+        }
+        offset += batch.num_rows();
+    }
+
+    Ok(offset)
+}
+
+/// Order matches the types returned by [`classify_csv`], mirroring arrow's CSV inference.
+static CSV_TYPES: LazyLock<RegexSet> = LazyLock::new(|| {
+    RegexSet::new([
+        r"(?i)^(true)$|^(false)$(?-i)",
+        r"^-?(\d+)$",
+        r"^-?((\d*\.\d+|\d+\.\d*)([eE][-+]?\d+)?|\d+([eE][-+]?\d+))$",
+        r"^\d{4}-\d\d-\d\d$",
+        r"^\d{4}-\d\d-\d\d[T ]\d\d:\d\d:\d\d(?:[^\d\.].*)?$",
+        r"^\d{4}-\d\d-\d\d[T ]\d\d:\d\d:\d\d\.\d{1,3}(?:[^\d].*)?$",
+        r"^\d{4}-\d\d-\d\d[T ]\d\d:\d\d:\d\d\.\d{1,6}(?:[^\d].*)?$",
+        r"^\d{4}-\d\d-\d\d[T ]\d\d:\d\d:\d\d\.\d{1,9}(?:[^\d].*)?$",
+    ])
+    .unwrap()
+});
+
+/// Returns the narrowest type that a CSV field could be read as.
+fn classify_csv(value: &str) -> &'static str {
+    const TYPES: [&str; 8] = [
+        "Boolean",
+        "Int64",
+        "Float64",
+        "Date32",
+        "Timestamp(s)",
+        "Timestamp(ms)",
+        "Timestamp(µs)",
+        "Timestamp(ns)",
+    ];
+    match CSV_TYPES.matches(value).into_iter().next() {
+        // Integers that overflow an i64 are read as strings.
+        Some(1) if value.len() >= 19 && value.parse::<i64>().is_err() => "Utf8",
+        Some(index) => TYPES[index],
+        None if matches!(value, "NaN" | "nan" | "inf" | "-inf") => "Float64",
+        None => "Utf8",
+    }
+}
+
+/// Returns the type that a non-null, non-string JSON value would be read as.
+fn classify_json(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "Null",
+        Value::Bool(_) => "Boolean",
+        Value::Number(number) if number.is_i64() => "Int64",
+        Value::Number(_) => "Float64",
+        Value::String(_) => "Utf8",
+        Value::Array(_) => "List",
+        Value::Object(_) => "Struct",
+    }
+}
+
+#[derive(Default)]
+struct ColumnProfile {
+    null_count: usize,
+    distinct: HashSet<u64>,
+    examples: Vec<String>,
+    candidate_types: Vec<String>,
+    widening_rows: Vec<usize>,
+}
+
+impl ColumnProfile {
+    fn observe_null(&mut self) {
+        self.null_count += 1;
+    }
+
+    fn observe(&mut self, row: usize, value: &str, candidate: &str) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        if self.distinct.insert(hasher.finish()) && self.examples.len() < MAX_EXAMPLES {
+            self.examples.push(value.to_string());
+        }
+
+        if !self.candidate_types.iter().any(|ty| ty == candidate) {
+            self.candidate_types.push(candidate.to_string());
+        }
+
+        if candidate == "Utf8" && self.widening_rows.len() < MAX_WIDENING_ROWS {
+            self.widening_rows.push(row);
+        }
+    }
+
+    fn finish(mut self, name: &str, data_type: &DataType) -> ColumnReport {
+        if self.candidate_types.iter().all(|ty| ty == "Utf8") {
+            self.widening_rows.clear();
+        }
+        ColumnReport {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            null_count: self.null_count,
+            distinct_count: self.distinct.len(),
+            examples: self.examples,
+            candidate_types: self.candidate_types,
+            widening_rows: self.widening_rows,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::datatypes::Field;
+
+    use super::*;
+
+    fn report_json(input: &[u8], fields: &[(&str, DataType)]) -> Vec<ColumnReport> {
+        let schema = Schema::new(
+            fields
+                .iter()
+                .map(|(name, data_type)| Field::new(*name, data_type.clone(), true))
+                .collect::<Vec<_>>(),
+        );
+        let mut profiles: Vec<_> = fields.iter().map(|_| ColumnProfile::default()).collect();
+        sample_json(input, false, None, &schema, usize::MAX, &mut profiles).unwrap();
+        schema
+            .fields()
+            .iter()
+            .zip(profiles)
+            .map(|(field, profile)| profile.finish(field.name(), field.data_type()))
+            .collect()
+    }
+
+    #[test]
+    fn classifies_csv_fields() {
+        assert_eq!(classify_csv("true"), "Boolean");
+        assert_eq!(classify_csv("-42"), "Int64");
+        assert_eq!(classify_csv("1.5e3"), "Float64");
+        assert_eq!(classify_csv("NaN"), "Float64");
+        assert_eq!(classify_csv("2024-01-31"), "Date32");
+        assert_eq!(classify_csv("2024-01-31T10:00:00"), "Timestamp(s)");
+        assert_eq!(classify_csv("99999999999999999999"), "Utf8");
+        assert_eq!(classify_csv("hello"), "Utf8");
+    }
+
+    #[test]
+    fn records_widening_rows() {
+        let input = b"{\"a\":1}\n{\"a\":2.5}\n{\"a\":\"x\"}\n{}\n{\"a\":\"x\"}";
+        let column = report_json(input, &[("a", DataType::Utf8)]).remove(0);
+        assert_eq!(column.null_count, 1);
+        assert_eq!(column.distinct_count, 3);
+        assert_eq!(column.examples, ["1", "2.5", "x"]);
+        assert_eq!(column.candidate_types, ["Int64", "Float64", "Utf8"]);
+        assert_eq!(column.widening_rows, [2, 4]);
+    }
+
+    #[test]
+    fn string_columns_have_no_widening_rows() {
+        let input = b"{\"a\":\"x\"}\n{\"a\":\"y\"}";
+        let column = report_json(input, &[("a", DataType::Utf8)]).remove(0);
+        assert_eq!(column.candidate_types, ["Utf8"]);
+        assert!(column.widening_rows.is_empty());
+    }
+}
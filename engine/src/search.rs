@@ -0,0 +1,259 @@
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::Schema;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::cell::{format_values, DisplayOptions};
+use crate::record_set::ColumnRef;
+
+/// The default number of hits returned by one search.
+const DEFAULT_LIMIT: usize = 100;
+
+/// The number of rows formatted at a time, so that finding a page of hits only formats the rows
+/// up to the last of them, rather than the rest of the batch.
+const CHUNK_ROWS: usize = 1024;
+
+#[derive(Tsify, Clone, Default, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchOptions {
+    /// Whether the query is a regular expression, rather than plain text.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub regex: bool,
+    #[tsify(optional)]
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Whether the query must match whole words, rather than any part of a value.
+    #[tsify(optional)]
+    #[serde(default)]
+    pub whole_word: bool,
+    /// The columns to search. Defaults to all of them.
+    #[tsify(optional)]
+    pub columns: Option<Vec<ColumnRef>>,
+    /// Only return hits after this cell, such as the `next` cell of a previous search.
+    #[tsify(optional)]
+    pub after: Option<CellPosition>,
+    /// The maximum number of hits to return. Defaults to 100.
+    #[tsify(optional)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Tsify, Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct CellPosition {
+    pub row: usize,
+    pub column: usize,
+}
+
+#[derive(Tsify, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults {
+    /// The matching cells, in row-major order.
+    pub hits: Vec<CellPosition>,
+    /// Whether there are more hits after the last one returned.
+    pub has_more: bool,
+}
+
+/// Finds the cells whose formatted values match `query`.
+pub fn search(
+    schema: &Schema,
+    batches: &[RecordBatch],
+    query: &str,
+    options: &SearchOptions,
+) -> Result<SearchResults, String> {
+    let limit = options.limit.unwrap_or(DEFAULT_LIMIT);
+    let mut hits = vec![];
+    if query.is_empty() {
+        return Ok(SearchResults { hits, has_more: false });
+    }
+
+    let pattern = build_pattern(query, options)?;
+    let mut columns = match &options.columns {
+        Some(columns) => columns
+            .iter()
+            .map(|column| column.index(schema))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => (0..schema.fields().len()).collect(),
+    };
+    columns.sort_unstable();
+    columns.dedup();
+
+    let first_row = options.after.map_or(0, |after| after.row);
+    let display = DisplayOptions::default();
+    let mut offset = 0;
+    for batch in batches {
+        let (batch_offset, num_rows) = (offset, batch.num_rows());
+        offset += num_rows;
+        if first_row >= offset {
+            continue;
+        }
+
+        // Only format the rows at or after the cursor.
+        let skip = first_row.saturating_sub(batch_offset);
+        for chunk_start in (skip..num_rows).step_by(CHUNK_ROWS) {
+            let chunk_rows = CHUNK_ROWS.min(num_rows - chunk_start);
+            let mut values = vec![];
+            for &column in &columns {
+                let array = batch.column(column).slice(chunk_start, chunk_rows);
+                values.push((column, array.clone(), format_values(&array, &display)?));
+            }
+
+            for row in 0..chunk_rows {
+                for (column, array, values) in &values {
+                    let position = CellPosition {
+                        row: batch_offset + chunk_start + row,
+                        column: *column,
+                    };
+                    if options.after.is_some_and(|after| position <= after)
+                        || array.is_null(row)
+                        || !pattern.is_match(&values[row])
+                    {
+                        continue;
+                    }
+                    if hits.len() == limit {
+                        return Ok(SearchResults { hits, has_more: true });
+                    }
+                    hits.push(position);
+                }
+            }
+        }
+    }
+
+    Ok(SearchResults { hits, has_more: false })
+}
+
+fn build_pattern(query: &str, options: &SearchOptions) -> Result<Regex, String> {
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let pattern = if options.whole_word {
+        format!(r"\b(?:{pattern})\b")
+    } else {
+        pattern
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field};
+
+    use super::*;
+
+    fn batches() -> (Schema, Vec<RecordBatch>) {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        let batch = |ids: Vec<i64>, names: Vec<Option<&str>>| {
+            RecordBatch::try_new(
+                Arc::new(schema.clone()),
+                vec![
+                    Arc::new(Int64Array::from(ids)),
+                    Arc::new(StringArray::from(names)),
+                ],
+            )
+            .unwrap()
+        };
+        let batches = vec![
+            batch(vec![1, 12], vec![Some("Cat"), Some("concatenate")]),
+            batch(vec![21, 3], vec![None, Some("cat 1")]),
+        ];
+        (schema, batches)
+    }
+
+    fn hits(query: &str, options: SearchOptions) -> Vec<(usize, usize)> {
+        let (schema, batches) = batches();
+        search(&schema, &batches, query, &options)
+            .unwrap()
+            .hits
+            .iter()
+            .map(|hit| (hit.row, hit.column))
+            .collect()
+    }
+
+    #[test]
+    fn finds_substrings_case_insensitively() {
+        assert_eq!(hits("cat", Default::default()), [(0, 1), (1, 1), (3, 1)]);
+        assert_eq!(
+            hits("1", Default::default()),
+            [(0, 0), (1, 0), (2, 0), (3, 1)]
+        );
+        assert_eq!(hits("", Default::default()), []);
+    }
+
+    #[test]
+    fn supports_matching_modes() {
+        let case_sensitive = SearchOptions {
+            case_sensitive: true,
+            ..Default::default()
+        };
+        assert_eq!(hits("Cat", case_sensitive), [(0, 1)]);
+
+        let whole_word = SearchOptions { whole_word: true, ..Default::default() };
+        assert_eq!(hits("cat", whole_word), [(0, 1), (3, 1)]);
+
+        let regex = SearchOptions {
+            regex: true,
+            columns: Some(vec![ColumnRef::Index(0)]),
+            ..Default::default()
+        };
+        assert_eq!(hits("^.1$", regex), [(2, 0)]);
+
+        let (schema, batches) = batches();
+        let regex = SearchOptions { regex: true, ..Default::default() };
+        assert!(search(&schema, &batches, "(", &regex).is_err());
+    }
+
+    #[test]
+    fn paginates_hits() {
+        let (schema, batches) = batches();
+        let mut options = SearchOptions { limit: Some(2), ..Default::default() };
+        let page = search(&schema, &batches, "1", &options).unwrap();
+        assert_eq!(page.hits.len(), 2);
+        assert!(page.has_more);
+
+        options.after = page.hits.last().copied();
+        let page = search(&schema, &batches, "1", &options).unwrap();
+        let hits: Vec<_> = page.hits.iter().map(|hit| (hit.row, hit.column)).collect();
+        assert_eq!(hits, [(2, 0), (3, 1)]);
+        assert!(!page.has_more);
+    }
+
+    #[test]
+    fn pages_through_large_batches() {
+        let schema = Schema::new(vec![Field::new("n", DataType::Int64, false)]);
+        let values = Int64Array::from_iter_values(0..5000);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(values)]).unwrap();
+        let batches = [batch];
+        let options = |after| SearchOptions {
+            regex: true,
+            after,
+            limit: Some(2),
+            ..Default::default()
+        };
+
+        let results = search(&schema, &batches, "^\\d*000$", &options(None)).unwrap();
+        let rows: Vec<_> = results.hits.iter().map(|hit| hit.row).collect();
+        assert_eq!(rows, [1000, 2000]);
+        assert!(results.has_more);
+
+        let after = results.hits.last().copied();
+        let results = search(&schema, &batches, "^\\d*000$", &options(after)).unwrap();
+        let rows: Vec<_> = results.hits.iter().map(|hit| hit.row).collect();
+        assert_eq!(rows, [3000, 4000]);
+        assert!(!results.has_more);
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::object_store::memory::InMemory;
+use datafusion::object_store::path::Path;
+use datafusion::object_store::ObjectStore;
+use datafusion_web_object_store::{HashMapResolver, WebObjectStore};
+use wasm_bindgen::JsCast;
+
+use crate::utils::read_blob_range;
+
+/// A file that plans and inference functions read from.
+///
+/// In the browser files are blobs, and natively they are local files, so the same engine can run
+/// in both.
+#[async_trait(?Send)]
+pub trait InputSource: Debug {
+    /// The file's name, such as `sales.csv`, if it has one.
+    fn name(&self) -> Option<String>;
+
+    fn size(&self) -> u64;
+
+    async fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>, String>;
+
+    async fn read(&self) -> Result<Vec<u8>, String> {
+        self.read_range(0..self.size()).await
+    }
+
+    /// Returns an object store that DataFusion can scan the file through, and the file's path in
+    /// that store.
+    async fn object_store(&self) -> Result<(Arc<dyn ObjectStore>, Path), String>;
+}
+
+/// A blob or file selected in the browser.
+#[derive(Debug, Clone)]
+pub struct BlobSource(web_sys::Blob);
+
+impl BlobSource {
+    pub fn new(blob: web_sys::Blob) -> Self {
+        Self(blob)
+    }
+}
+
+#[async_trait(?Send)]
+impl InputSource for BlobSource {
+    fn name(&self) -> Option<String> {
+        self.0.dyn_ref::<web_sys::File>().map(|file| file.name())
+    }
+
+    fn size(&self) -> u64 {
+        self.0.size() as u64
+    }
+
+    async fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>, String> {
+        read_blob_range(&self.0, range).await
+    }
+
+    async fn object_store(&self) -> Result<(Arc<dyn ObjectStore>, Path), String> {
+        let mut files = HashMapResolver::new();
+        files.insert("0".to_string(), self.0.clone());
+        Ok((Arc::new(WebObjectStore::new(files)), Path::from("0")))
+    }
+}
+
+/// A file that has already been read into memory.
+#[derive(Debug, Clone)]
+pub struct BytesSource {
+    name: Option<String>,
+    bytes: Arc<[u8]>,
+}
+
+impl BytesSource {
+    pub fn new(bytes: impl Into<Arc<[u8]>>) -> Self {
+        Self { name: None, bytes: bytes.into() }
+    }
+
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        Self { name: Some(name.into()), ..self }
+    }
+}
+
+#[async_trait(?Send)]
+impl InputSource for BytesSource {
+    fn name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    fn size(&self) -> u64 {
+        self.bytes.len() as u64
+    }
+
+    async fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>, String> {
+        let end = range.end.min(self.size()) as usize;
+        let start = (range.start as usize).min(end);
+        Ok(self.bytes[start..end].to_vec())
+    }
+
+    async fn object_store(&self) -> Result<(Arc<dyn ObjectStore>, Path), String> {
+        let store = InMemory::new();
+        let path = Path::from("0");
+        store
+            .put(&path, self.bytes.to_vec().into())
+            .await
+            .map_err(|err| err.to_string())?;
+        Ok((Arc::new(store), path))
+    }
+}
+
+/// A file on the local file system, when running natively.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct LocalFileSource {
+    path: std::path::PathBuf,
+    size: u64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LocalFileSource {
+    pub fn new(path: impl AsRef<std::path::Path>) -> Result<Self, String> {
+        let path = std::fs::canonicalize(path).map_err(|err| err.to_string())?;
+        let size = std::fs::metadata(&path)
+            .map_err(|err| err.to_string())?
+            .len();
+        Ok(Self { path, size })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait(?Send)]
+impl InputSource for LocalFileSource {
+    fn name(&self) -> Option<String> {
+        let name = self.path.file_name()?;
+        Some(name.to_string_lossy().into_owned())
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    async fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>, String> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let mut file = std::fs::File::open(&self.path).map_err(|err| err.to_string())?;
+        file.seek(SeekFrom::Start(range.start))
+            .map_err(|err| err.to_string())?;
+        let mut bytes = vec![];
+        file.take(range.end.saturating_sub(range.start))
+            .read_to_end(&mut bytes)
+            .map_err(|err| err.to_string())?;
+        Ok(bytes)
+    }
+
+    async fn object_store(&self) -> Result<(Arc<dyn ObjectStore>, Path), String> {
+        let path = Path::from_absolute_path(&self.path).map_err(|err| err.to_string())?;
+        Ok((Arc::new(object_store::local::LocalFileSystem::new()), path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    fn sales_csv() -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../data/sales.csv")
+    }
+
+    #[test]
+    fn reads_ranges_of_bytes() {
+        let source = BytesSource::new(&b"id,name\n1,a\n"[..]).with_name("test.csv");
+        assert_eq!(source.name().as_deref(), Some("test.csv"));
+        assert_eq!(block_on(source.read_range(3..7)).unwrap(), b"name");
+        assert_eq!(block_on(source.read_range(11..20)).unwrap(), b"\n");
+
+        let (store, path) = block_on(source.object_store()).unwrap();
+        let bytes = block_on(async { store.get(&path).await?.bytes().await }).unwrap();
+        assert_eq!(&bytes[..], b"id,name\n1,a\n");
+    }
+
+    #[test]
+    fn reads_local_files() {
+        let source = LocalFileSource::new(sales_csv()).unwrap();
+        assert_eq!(source.name().as_deref(), Some("sales.csv"));
+        let header = block_on(source.read_range(0..12)).unwrap();
+        assert_eq!(header, b"\"Sales Team\"");
+
+        let (store, path) = block_on(source.object_store()).unwrap();
+        let meta = block_on(store.head(&path)).unwrap();
+        assert_eq!(meta.size, source.size());
+
+        assert!(LocalFileSource::new("missing.csv").is_err());
+    }
+}
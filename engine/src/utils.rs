@@ -1,5 +1,6 @@
 use std::ops::Range;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
 
 #[cfg(feature = "console_error_panic_hook")]
 #[wasm_bindgen(start)]
@@ -7,9 +8,45 @@ fn set_panic_hook() {
     console_error_panic_hook::set_once();
 }
 
+/// The current time in milliseconds, read from the JavaScript clock in the browser, where
+/// `std::time` is unsupported.
+#[cfg(target_arch = "wasm32")]
+pub fn now_millis() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_millis() -> f64 {
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+    now.map_or(0.0, |duration| duration.as_secs_f64() * 1000.0)
+}
+
 pub fn chunk_ranges(size: u64, chunk_size: usize) -> impl Iterator<Item = (Range<u64>, bool)> {
     (0..size).step_by(chunk_size).map(move |start| {
         let end = (start + chunk_size as u64).min(size);
         (start..end, end == size)
     })
 }
+
+pub async fn read_blob(blob: &web_sys::Blob) -> Result<Vec<u8>, String> {
+    let bytes = JsFuture::from(blob.array_buffer())
+        .await
+        .map_err(|_| "cannot read file".to_string())?;
+    Ok(js_sys::Uint8Array::new(&bytes).to_vec())
+}
+
+/// Reads part of a blob, without reading the rest of it.
+pub async fn read_blob_range(blob: &web_sys::Blob, range: Range<u64>) -> Result<Vec<u8>, String> {
+    let blob = blob
+        .slice_with_f64_and_f64(range.start as f64, range.end as f64)
+        .map_err(|_| "cannot read file".to_string())?;
+    read_blob(&blob).await
+}
+
+pub fn bytes_to_blob_with_type(bytes: &[u8], mime_type: &str) -> Result<web_sys::Blob, String> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime_type);
+    web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+        .map_err(|_| "cannot create blob".to_string())
+}
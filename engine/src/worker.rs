@@ -0,0 +1,458 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use futures::future::{AbortHandle, Abortable};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::file_format::FileFormat;
+use crate::malformed::MalformedRowPolicy;
+use crate::metrics::ExecutionMetrics;
+use crate::plan::{Plan, PlanStep};
+use crate::record_set::{ColumnRef, RecordSet};
+use crate::schema_report::SchemaReport;
+use crate::source::{BlobSource, InputSource};
+
+/// Identifies a file, plan or record set held by a [`WorkerHost`].
+#[derive(Tsify, Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Handle(pub u32);
+
+#[derive(Tsify, Deserialize, Debug)]
+#[tsify(from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestMessage {
+    /// Chosen by the sender, and repeated in the response so it can be matched to the request.
+    pub id: u32,
+    pub request: Request,
+}
+
+#[derive(Tsify, Deserialize, Debug)]
+#[tsify(from_wasm_abi)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Request {
+    /// Keeps a file, so that later requests can refer to it by its handle.
+    OpenFile {
+        #[tsify(type = "Blob")]
+        #[serde(with = "serde_wasm_bindgen::preserve")]
+        file: web_sys::Blob,
+    },
+    InferFormat {
+        file: Handle,
+    },
+    /// Infers the schema of a file, which is then used by `readFile` requests for the file.
+    #[serde(rename_all = "camelCase")]
+    InferSchema {
+        file: Handle,
+        format: FileFormat,
+        #[tsify(optional)]
+        max_records: Option<usize>,
+        #[tsify(optional)]
+        #[serde(default)]
+        report: bool,
+    },
+    /// Creates a plan that scans a file, with its inferred schema.
+    #[serde(rename_all = "camelCase")]
+    ReadFile {
+        file: Handle,
+        format: FileFormat,
+        #[tsify(optional)]
+        on_malformed: Option<MalformedRowPolicy>,
+    },
+    /// Creates a plan by applying steps to another plan, which is left unchanged.
+    BuildPlan {
+        plan: Handle,
+        steps: Vec<PlanStep>,
+    },
+    /// Executes a plan and keeps its results as a record set. Can be stopped by a `cancel` request.
+    Execute {
+        plan: Handle,
+    },
+    /// Encodes the rows in `start..end` of a record set, optionally with only the given columns.
+    FetchRows {
+        records: Handle,
+        start: usize,
+        end: usize,
+        #[tsify(optional)]
+        columns: Option<Vec<ColumnRef>>,
+    },
+    /// Stops the execution started by the request with the given id.
+    ///
+    /// Executions stop the next time they wait to read from a file, and respond with an error.
+    Cancel {
+        request: u32,
+    },
+    /// Drops a file, plan or record set, so its handle can no longer be used.
+    Release {
+        handle: Handle,
+    },
+}
+
+#[derive(Tsify, Serialize, Debug)]
+#[tsify(into_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseMessage {
+    /// The id of the request being responded to.
+    pub id: u32,
+    pub response: Response,
+}
+
+#[derive(Tsify, Serialize, Debug)]
+#[tsify(into_wasm_abi)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Response {
+    FileOpened {
+        file: Handle,
+        #[tsify(optional)]
+        name: Option<String>,
+        size: u64,
+    },
+    Format {
+        format: FileFormat,
+    },
+    Schema {
+        /// The schema, encoded as an IPC message.
+        #[tsify(type = "Uint8Array")]
+        #[serde(with = "serde_bytes")]
+        schema: Vec<u8>,
+        #[tsify(optional)]
+        report: Option<SchemaReport>,
+    },
+    Plan {
+        plan: Handle,
+    },
+    #[serde(rename_all = "camelCase")]
+    Executed {
+        records: Handle,
+        num_rows: usize,
+        metrics: ExecutionMetrics,
+    },
+    Rows {
+        /// An IPC stream of a schema message followed by the rows.
+        #[tsify(type = "Uint8Array")]
+        #[serde(with = "serde_bytes")]
+        bytes: Vec<u8>,
+    },
+    Done,
+    Error {
+        message: String,
+    },
+}
+
+/// Runs engine requests inside a Web Worker, so that the main thread only renders their results.
+///
+/// Files, plans and record sets stay in the worker, and are referred to by handles.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WorkerHost {
+    next_handle: Cell<u32>,
+    files: RefCell<HashMap<Handle, Arc<dyn InputSource>>>,
+    /// The schemas last inferred for each file.
+    schemas: RefCell<HashMap<Handle, SchemaRef>>,
+    plans: RefCell<HashMap<Handle, Plan>>,
+    record_sets: RefCell<HashMap<Handle, Rc<RecordSet>>>,
+    /// Aborts the executions in progress, by the id of the request that started them.
+    executions: RefCell<HashMap<u32, AbortHandle>>,
+}
+
+#[wasm_bindgen]
+impl WorkerHost {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles a request. Failures are returned as error responses, rather than thrown.
+    pub async fn handle(&self, message: RequestMessage) -> ResponseMessage {
+        let response = self
+            .respond(message.id, message.request)
+            .await
+            .unwrap_or_else(|message| Response::Error { message });
+        ResponseMessage { id: message.id, response }
+    }
+}
+
+impl WorkerHost {
+    pub fn open_source(&self, source: Arc<dyn InputSource>) -> Handle {
+        let handle = self.next_handle();
+        self.files.borrow_mut().insert(handle, source);
+        handle
+    }
+
+    async fn respond(&self, id: u32, request: Request) -> Result<Response, String> {
+        Ok(match request {
+            Request::OpenFile { file } => {
+                let source = Arc::new(BlobSource::new(file));
+                let (name, size) = (source.name(), source.size());
+                let file = self.open_source(source);
+                Response::FileOpened { file, name, size }
+            }
+            Request::InferFormat { file } => {
+                let format = crate::infer::infer_format(self.file(file)?.as_ref()).await?;
+                Response::Format { format }
+            }
+            Request::InferSchema { file, format, max_records, report } => {
+                let source = self.file(file)?;
+                let (schema, report) =
+                    crate::infer::infer_schema(source.as_ref(), &format, max_records, report)
+                        .await?;
+                self.schemas.borrow_mut().insert(file, schema.clone());
+                let schema = RecordSet::schema_message(&schema);
+                Response::Schema { schema, report }
+            }
+            Request::ReadFile { file, format, on_malformed } => {
+                let source = self.file(file)?;
+                let schema = self.schemas.borrow().get(&file).cloned();
+                let schema = match schema {
+                    Some(schema) => schema,
+                    None => {
+                        crate::infer::infer_schema(source.as_ref(), &format, None, false)
+                            .await?
+                            .0
+                    }
+                };
+                let plan = Plan::read_source(source, format, schema, on_malformed).await?;
+                Response::Plan { plan: self.insert_plan(plan) }
+            }
+            Request::BuildPlan { plan, steps } => {
+                let mut plan = self.plan(plan)?;
+                for step in steps {
+                    plan = plan.apply(step).await?;
+                }
+                Response::Plan { plan: self.insert_plan(plan) }
+            }
+            Request::Execute { plan } => {
+                let plan = self.plan(plan)?;
+                let (abort, registration) = AbortHandle::new_pair();
+                self.executions.borrow_mut().insert(id, abort);
+                let result = Abortable::new(plan.collect(), registration).await;
+                self.executions.borrow_mut().remove(&id);
+
+                let records = result.map_err(|_| "the execution was cancelled".to_string())??;
+                let num_rows = records.num_rows();
+                let metrics = records.metrics().unwrap_or_default();
+                let handle = self.next_handle();
+                self.record_sets
+                    .borrow_mut()
+                    .insert(handle, Rc::new(records));
+                Response::Executed { records: handle, num_rows, metrics }
+            }
+            Request::FetchRows { records, start, end, columns } => {
+                let records = self.record_set(records)?;
+                if start > end {
+                    return Err(format!("invalid row range: {start}..{end}"));
+                }
+                let bytes = match columns {
+                    Some(columns) => {
+                        let mut bytes = records.encode_schema_projected(columns.clone())?;
+                        bytes.extend(records.encode_rows_projected(start, end, columns)?);
+                        bytes
+                    }
+                    None => {
+                        let mut bytes = records.encode_schema();
+                        bytes.extend(records.encode_rows(start, end));
+                        bytes
+                    }
+                };
+                Response::Rows { bytes }
+            }
+            Request::Cancel { request } => {
+                if let Some(execution) = self.executions.borrow_mut().remove(&request) {
+                    execution.abort();
+                }
+                Response::Done
+            }
+            Request::Release { handle } => {
+                self.schemas.borrow_mut().remove(&handle);
+                let released = self.files.borrow_mut().remove(&handle).is_some()
+                    || self.plans.borrow_mut().remove(&handle).is_some()
+                    || self.record_sets.borrow_mut().remove(&handle).is_some();
+                if !released {
+                    return Err(format!("unknown handle: {}", handle.0));
+                }
+                Response::Done
+            }
+        })
+    }
+
+    fn next_handle(&self) -> Handle {
+        let handle = self.next_handle.get();
+        self.next_handle.set(handle + 1);
+        Handle(handle)
+    }
+
+    fn insert_plan(&self, plan: Plan) -> Handle {
+        let handle = self.next_handle();
+        self.plans.borrow_mut().insert(handle, plan);
+        handle
+    }
+
+    fn file(&self, handle: Handle) -> Result<Arc<dyn InputSource>, String> {
+        let files = self.files.borrow();
+        let file = files
+            .get(&handle)
+            .ok_or(format!("unknown file: {}", handle.0))?;
+        Ok(file.clone())
+    }
+
+    fn plan(&self, handle: Handle) -> Result<Plan, String> {
+        let plans = self.plans.borrow();
+        let plan = plans
+            .get(&handle)
+            .ok_or(format!("unknown plan: {}", handle.0))?;
+        Ok(plan.clone())
+    }
+
+    fn record_set(&self, handle: Handle) -> Result<Rc<RecordSet>, String> {
+        let record_sets = self.record_sets.borrow();
+        let records = record_sets
+            .get(&handle)
+            .ok_or(format!("unknown record set: {}", handle.0))?;
+        Ok(records.clone())
+    }
+}
+
+/// Handles the messages posted to the current worker, replying to each with a response message.
+///
+/// Requests are handled concurrently, so that an execution can be cancelled while it runs.
+#[wasm_bindgen]
+pub fn start_worker() -> Result<(), JsValue> {
+    let scope: web_sys::DedicatedWorkerGlobalScope = js_sys::global().dyn_into()?;
+    let host = Rc::new(WorkerHost::new());
+    let reply_to = scope.clone();
+    let on_message = Closure::<dyn Fn(_)>::new(move |event: web_sys::MessageEvent| {
+        let (host, scope) = (host.clone(), reply_to.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            let message = handle_message(&host, event.data()).await;
+            let posted = message
+                .into_js()
+                .map_err(JsValue::from)
+                .and_then(|message| scope.post_message(&message));
+            if let Err(err) = posted {
+                web_sys::console::error_1(&err);
+            }
+        });
+    });
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    on_message.forget();
+    Ok(())
+}
+
+async fn handle_message(host: &WorkerHost, data: JsValue) -> ResponseMessage {
+    match RequestMessage::from_js(data.clone()) {
+        Ok(message) => host.handle(message).await,
+        Err(err) => {
+            // Reply to malformed requests too, so that the sender isn't left waiting.
+            let id = js_sys::Reflect::get(&data, &"id".into())
+                .ok()
+                .and_then(|id| id.as_f64())
+                .unwrap_or_default();
+            ResponseMessage {
+                id: id as u32,
+                response: Response::Error { message: err.to_string() },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::RecordBatch;
+    use datafusion::arrow::ipc::reader::StreamReader;
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::source::BytesSource;
+
+    fn request(host: &WorkerHost, request: Request) -> Response {
+        let message = RequestMessage { id: 1, request };
+        block_on(host.handle(message)).response
+    }
+
+    #[test]
+    fn runs_requests_against_handles() {
+        let host = WorkerHost::new();
+        let csv = "id,name\n1,a\n2,b\n3,c\n";
+        let file = host.open_source(Arc::new(
+            BytesSource::new(csv.as_bytes()).with_name("t.csv"),
+        ));
+
+        let Response::Format { format } = request(&host, Request::InferFormat { file }) else {
+            panic!("expected a format");
+        };
+        let infer = Request::InferSchema {
+            file,
+            format: format.clone(),
+            max_records: None,
+            report: false,
+        };
+        assert!(matches!(request(&host, infer), Response::Schema { .. }));
+
+        let read = Request::ReadFile { file, format, on_malformed: None };
+        let Response::Plan { plan } = request(&host, read) else {
+            panic!("expected a plan");
+        };
+        let steps = vec![PlanStep::Limit { skip: 1, fetch: Some(5) }];
+        let Response::Plan { plan: limited } = request(&host, Request::BuildPlan { plan, steps })
+        else {
+            panic!("expected a plan");
+        };
+        assert_ne!(plan, limited);
+
+        let Response::Executed { records, num_rows, metrics } =
+            request(&host, Request::Execute { plan: limited })
+        else {
+            panic!("expected results");
+        };
+        assert_eq!(num_rows, 2);
+        assert_eq!(metrics.output_rows, 2);
+
+        let fetch = Request::FetchRows {
+            records,
+            start: 0,
+            end: 2,
+            columns: Some(vec![ColumnRef::Name("name".to_string())]),
+        };
+        let Response::Rows { bytes } = request(&host, fetch) else {
+            panic!("expected rows");
+        };
+        let batches: Vec<RecordBatch> = StreamReader::try_new(std::io::Cursor::new(bytes), None)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(batches[0].num_columns(), 1);
+        assert_eq!(batches[0].num_rows(), 2);
+
+        let fetch = Request::FetchRows { records, start: 2, end: 1, columns: None };
+        assert!(matches!(request(&host, fetch), Response::Error { .. }));
+    }
+
+    #[test]
+    fn reports_errors_for_released_handles() {
+        let host = WorkerHost::new();
+        let file = host.open_source(Arc::new(BytesSource::new(&b"a\n1\n"[..])));
+        assert!(matches!(
+            request(&host, Request::Release { handle: file }),
+            Response::Done
+        ));
+
+        let Response::Error { message } = request(&host, Request::InferFormat { file }) else {
+            panic!("expected an error");
+        };
+        assert_eq!(message, "unknown file: 0");
+        assert!(matches!(
+            request(&host, Request::Release { handle: file }),
+            Response::Error { .. }
+        ));
+        // Cancelling a request that isn't running does nothing.
+        assert!(matches!(
+            request(&host, Request::Cancel { request: 7 }),
+            Response::Done
+        ));
+    }
+}
@@ -0,0 +1,231 @@
+//! End-to-end tests that run the engine natively against the sample data files.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    Array, AsArray, Float64Array, Int64Array, RecordBatch, StringArray,
+};
+use datafusion::arrow::datatypes::{DataType, Field, Float64Type, Int64Type, Schema, SchemaRef};
+use datafusion::arrow::ipc::reader::StreamReader;
+use datafusion::parquet::arrow::ArrowWriter;
+use datafusion::parquet::file::properties::WriterProperties;
+use engine::{
+    infer, BytesSource, ExportFormat, FileFormat, InputSource, LocalFileSource, Plan, RecordSet,
+};
+use futures::executor::block_on;
+
+fn sample_file(name: &str) -> Arc<dyn InputSource> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../data")
+        .join(name);
+    Arc::new(LocalFileSource::new(path).unwrap())
+}
+
+fn infer(source: &dyn InputSource) -> (FileFormat, SchemaRef) {
+    let format = block_on(infer::infer_format(source)).unwrap();
+    let (schema, _) = block_on(infer::infer_schema(source, &format, None, false)).unwrap();
+    (format, schema)
+}
+
+fn read(source: Arc<dyn InputSource>) -> Plan {
+    let (format, schema) = infer(source.as_ref());
+    block_on(Plan::read_source(source, format, schema, None)).unwrap()
+}
+
+/// Decodes the IPC messages that the app reads a record set through.
+fn decode(records: &RecordSet, start: usize, end: usize) -> Vec<RecordBatch> {
+    let mut bytes = records.encode_schema();
+    bytes.extend(records.encode_rows(start, end));
+    StreamReader::try_new(std::io::Cursor::new(bytes), None)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+}
+
+fn column_type(schema: &SchemaRef, name: &str) -> DataType {
+    schema.field_with_name(name).unwrap().data_type().clone()
+}
+
+fn strings(batch: &RecordBatch, name: &str) -> Vec<Option<String>> {
+    let column = batch.column_by_name(name).unwrap().as_string::<i32>();
+    column
+        .iter()
+        .map(|value| value.map(str::to_string))
+        .collect()
+}
+
+#[test]
+fn infers_sample_files() {
+    // One of the distributors in `sales.csv` has a name with non-ASCII bytes.
+    for (name, encoding) in [("sales.csv", "ISO-8859-1"), ("sales2.csv", "ascii")] {
+        let source = sample_file(name);
+        let inferred = block_on(infer::infer_encoding(source.as_ref())).unwrap();
+        assert_eq!(inferred, encoding);
+
+        let (format, schema) = infer(source.as_ref());
+        assert_eq!(
+            format,
+            FileFormat::Csv {
+                encoding: encoding.to_string(),
+                has_headers: true,
+            }
+        );
+        assert_eq!(schema.fields().len(), 29);
+        assert_eq!(column_type(&schema, "Sales Team"), DataType::Utf8);
+        assert_eq!(column_type(&schema, "Quantity Shipped"), DataType::Int64);
+        assert_eq!(column_type(&schema, "Net Sales"), DataType::Float64);
+        assert_eq!(column_type(&schema, "Shipment Year"), DataType::Int64);
+        // The column is empty in every row.
+        assert_eq!(column_type(&schema, "Fiscal Month old"), DataType::Null);
+    }
+}
+
+#[test]
+fn reads_sample_files() {
+    for (name, num_rows) in [("sales.csv", 1041), ("sales2.csv", 599)] {
+        let records = block_on(read(sample_file(name)).collect()).unwrap();
+        assert_eq!(records.num_rows(), num_rows);
+
+        let batches = decode(&records, 0, num_rows);
+        let total: usize = batches.iter().map(RecordBatch::num_rows).sum();
+        assert_eq!(total, num_rows);
+
+        let batch = &batches[0];
+        assert_eq!(
+            strings(batch, "Owner")[..2],
+            [
+                Some("Thitisak Surachai".to_string()),
+                Some("Ziad Taha".to_string())
+            ]
+        );
+        let quantities = batch["Quantity Shipped"].as_primitive::<Int64Type>();
+        assert_eq!(quantities.values()[..2], [8, 90]);
+        let net_sales = batch["Net Sales_USD"].as_primitive::<Float64Type>();
+        assert_eq!(net_sales.values()[..2], [3600.0, 49320.46]);
+    }
+}
+
+#[test]
+fn limits_sample_files() {
+    let plan = read(sample_file("sales.csv")).limit(1, Some(3)).unwrap();
+    let records = block_on(plan.collect()).unwrap();
+    assert_eq!(records.num_rows(), 3);
+
+    let batches = decode(&records, 0, 3);
+    let owners = strings(&batches[0], "Owner");
+    assert_eq!(owners[0].as_deref(), Some("Ziad Taha"));
+
+    // Encoding a range only returns the rows in it.
+    let batches = decode(&records, 1, 2);
+    let total: usize = batches.iter().map(RecordBatch::num_rows).sum();
+    assert_eq!(total, 1);
+}
+
+#[test]
+fn reads_generated_json() {
+    let lines: String = (0..100)
+        .map(|i| {
+            format!(
+                "{{\"id\": {i}, \"name\": \"row {i}\", \"score\": {}}}\n",
+                i as f64 / 4.0
+            )
+        })
+        .collect();
+    let source = Arc::new(BytesSource::new(lines.into_bytes()).with_name("rows.json"));
+
+    let (format, schema) = infer(source.as_ref());
+    assert!(matches!(
+        format,
+        FileFormat::Json { concatenated: false, .. }
+    ));
+    assert_eq!(column_type(&schema, "id"), DataType::Int64);
+    assert_eq!(column_type(&schema, "name"), DataType::Utf8);
+    assert_eq!(column_type(&schema, "score"), DataType::Float64);
+
+    let plan = read(source).limit(10, Some(5)).unwrap();
+    let records = block_on(plan.collect()).unwrap();
+    let batches = decode(&records, 0, records.num_rows());
+    let ids: Vec<i64> = batches
+        .iter()
+        .flat_map(|batch| batch["id"].as_primitive::<Int64Type>().values().to_vec())
+        .collect();
+    assert_eq!(ids, [10, 11, 12, 13, 14]);
+    assert_eq!(strings(&batches[0], "name")[0].as_deref(), Some("row 10"));
+}
+
+#[test]
+fn reads_generated_parquet() {
+    let format = ExportFormat::Parquet { compression: None };
+    let bytes = block_on(read(sample_file("sales.csv")).export_bytes(&format)).unwrap();
+    let source = Arc::new(BytesSource::new(bytes).with_name("sales.parquet"));
+
+    let (format, schema) = infer(source.as_ref());
+    assert!(matches!(format, FileFormat::Parquet { .. }));
+    assert_eq!(column_type(&schema, "Quantity Shipped"), DataType::Int64);
+    assert_eq!(column_type(&schema, "Net Sales"), DataType::Float64);
+
+    let records = block_on(read(source).collect()).unwrap();
+    assert_eq!(records.num_rows(), 1041);
+    let batches = decode(&records, 0, 1);
+    assert_eq!(batches[0].num_rows(), 1);
+    assert_eq!(batches[0]["Fiscal Month old"].logical_null_count(), 1);
+    assert_eq!(
+        strings(&batches[0], "Sales Team")[0].as_deref(),
+        Some("Asia Sales Team")
+    );
+}
+
+#[test]
+fn reads_parquet_written_by_arrow() {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("score", DataType::Float64, false),
+    ]));
+    let names = (0..200).map(|i| (i % 10 != 0).then(|| format!("row {i}")));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int64Array::from_iter_values(0..200)),
+            Arc::new(names.collect::<StringArray>()),
+            Arc::new(Float64Array::from_iter_values(
+                (0..200).map(|i| i as f64 / 4.0),
+            )),
+        ],
+    )
+    .unwrap();
+    // Splits the rows between row groups, so that reading them crosses a boundary.
+    let properties = WriterProperties::builder()
+        .set_max_row_group_size(64)
+        .build();
+    let mut writer = ArrowWriter::try_new(vec![], schema, Some(properties)).unwrap();
+    writer.write(&batch).unwrap();
+    let bytes = writer.into_inner().unwrap();
+    let source = Arc::new(BytesSource::new(bytes).with_name("rows.parquet"));
+
+    let (format, schema) = infer(source.as_ref());
+    assert!(matches!(format, FileFormat::Parquet { .. }));
+    assert_eq!(column_type(&schema, "id"), DataType::Int64);
+    assert_eq!(column_type(&schema, "name"), DataType::Utf8);
+    assert_eq!(column_type(&schema, "score"), DataType::Float64);
+
+    let plan = read(source).limit(126, Some(5)).unwrap();
+    let records = block_on(plan.collect()).unwrap();
+    let batches = decode(&records, 0, records.num_rows());
+    let ids: Vec<i64> = batches
+        .iter()
+        .flat_map(|batch| batch["id"].as_primitive::<Int64Type>().values().to_vec())
+        .collect();
+    assert_eq!(ids, [126, 127, 128, 129, 130]);
+    let names: Vec<_> = batches
+        .iter()
+        .flat_map(|batch| strings(batch, "name"))
+        .collect();
+    assert_eq!(names[4], None);
+    assert_eq!(names[0].as_deref(), Some("row 126"));
+    let scores = batches[0]["score"].as_primitive::<Float64Type>();
+    assert_eq!(scores.value(0), 31.5);
+}
@@ -3,11 +3,29 @@
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
+use engine::{infer_file_format, infer_file_schema, FileFormat};
+use js_sys::{Array, Uint8Array};
 use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
+fn file(name: &str, contents: &str) -> web_sys::File {
+    let parts = Array::of1(&Uint8Array::from(contents.as_bytes()));
+    web_sys::File::new_with_u8_array_sequence(&parts, name).unwrap()
+}
+
 #[wasm_bindgen_test]
-fn pass() {
-    assert_eq!(1 + 1, 2);
+async fn infers_files() {
+    let file = file("rows.csv", "id,name\n1,first\n2,second\n");
+    let format = infer_file_format(&file).await.unwrap();
+    assert!(matches!(format, FileFormat::Csv { has_headers: true, .. }));
+
+    let schema = infer_file_schema(&file, format, None, None).await.unwrap();
+    let names: Vec<_> = schema
+        .inner()
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .collect();
+    assert_eq!(names, ["id", "name"]);
 }